@@ -0,0 +1,116 @@
+#ifndef TETRIS_H
+#define TETRIS_H
+
+/* Generated by cbindgen from src/ffi.rs; do not edit. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * Opaque game handle.
+ */
+typedef struct TetrisGame TetrisGame;
+
+/**
+ * Creates a game from command-line style flags (`-seed`, `-startlevel`,
+ * `-scriptfile1`, `-scriptfile2`); NULL means the defaults. Returns NULL on
+ * error.
+ *
+ * # Safety
+ * `args` must be NULL or a valid NUL-terminated string.
+ */
+struct TetrisGame *tetris_new(const char *args);
+
+/**
+ * Frees a game from `tetris_new`; NULL is ignored.
+ */
+void tetris_free(struct TetrisGame *game);
+
+/**
+ * Runs a line of text-mode commands for the current player (`"3left cw drop"`,
+ * `"levelup"`, `"macro m left drop"`, ...). While a special action is pending
+ * the next command must be `blind`, `heavy` or `force <block>`. Returns 0, or
+ * -1 at the first command that fails (earlier ones stay applied).
+ *
+ * # Safety
+ * `game` must be NULL or a live handle, and `line` NULL or a valid
+ * NUL-terminated string.
+ */
+int tetris_command(struct TetrisGame *game, const char *line);
+
+/**
+ * Message of the last failed call on this thread, or NULL. Owned by the library.
+ */
+const char *tetris_last_error(void);
+
+/**
+ * Player to move (1 or 2), or 0 for NULL.
+ */
+int tetris_current_player(const struct TetrisGame *game);
+
+bool tetris_running(const struct TetrisGame *game);
+
+/**
+ * Player who lost, or 0 while nobody has.
+ */
+int tetris_loser(const struct TetrisGame *game);
+
+/**
+ * Player who has to choose a special action next, or 0.
+ */
+int tetris_awaiting_special(const struct TetrisGame *game);
+
+/**
+ * Current score of `player`, or -1 for a bad player.
+ */
+int tetris_score(const struct TetrisGame *game, int player);
+
+/**
+ * Current level of `player`, or -1 for a bad player.
+ */
+int tetris_level(const struct TetrisGame *game, int player);
+
+int tetris_hi_score(const struct TetrisGame *game);
+
+/**
+ * Rows in the play area, as read by `tetris_cell`.
+ */
+int tetris_rows(void);
+
+/**
+ * Columns in the play area.
+ */
+int tetris_cols(void);
+
+/**
+ * Cell of `player`'s play area, `row` 0 at the top: `' '` when empty,
+ * otherwise the block letter (the falling block included). Returns 0 when out
+ * of range.
+ */
+char tetris_cell(const struct TetrisGame *game, int player, int row, int col);
+
+/**
+ * Full state as JSON, in the same shape as the network `state` message.
+ * Free with `tetris_string_free`.
+ */
+char *tetris_state_json(const struct TetrisGame *game);
+
+/**
+ * Events since the last call (clears, special actions, game over) as a JSON
+ * array. Free with `tetris_string_free`.
+ */
+char *tetris_events_json(struct TetrisGame *game);
+
+/**
+ * Frees a string returned by this library; NULL is ignored.
+ *
+ * # Safety
+ * `s` must be NULL or a string from `tetris_state_json` / `tetris_events_json`
+ * that has not been freed yet.
+ */
+void tetris_string_free(char *s);
+
+#endif  /* TETRIS_H */
@@ -0,0 +1,129 @@
+//! Machine-readable output for `text -batch`: one JSON object per line on
+//! stdout for every command applied, then a summary line.
+//!
+//! ```text
+//! {"type":"command","command":"left","repeat":3,"player":1,"outcome":"moved","score":0,"board_hash":"..."}
+//! {"type":"command","command":"lfet","repeat":1,"player":1,"outcome":"invalid","message":"Invalid command: lfet",...}
+//! {"type":"summary","commands":2,"invalid":1,"errors":0,"running":true,"loser":null,"scores":[0,0],...}
+//! ```
+
+use serde::Serialize;
+
+use crate::game::Game;
+use crate::player::MoveOutcome;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CommandRecord {
+    /// The command after alias and abbreviation resolution, or the raw token
+    /// if it was invalid; `computer` for a controller's turn.
+    pub command: String,
+    pub repeat: i32,
+    /// The player who issued the command.
+    pub player: i32,
+    /// `moved`, `blocked` or `landed` for movement, `ok` for other commands,
+    /// `error` when a valid command failed and `invalid` when it was not a command.
+    pub outcome: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    /// That player's score and board after the command.
+    pub score: i32,
+    pub board_hash: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Summary {
+    pub commands: u32,
+    pub invalid: u32,
+    pub errors: u32,
+    pub running: bool,
+    pub loser: Option<i32>,
+    pub scores: [i32; 2],
+    pub hi_score: i32,
+    pub board_hashes: [String; 2],
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Line<'a> {
+    Command(&'a CommandRecord),
+    Summary(&'a Summary),
+}
+
+pub fn outcome_name(outcome: MoveOutcome) -> &'static str {
+    match outcome {
+        MoveOutcome::Moved => "moved",
+        MoveOutcome::Blocked => "blocked",
+        MoveOutcome::Landed => "landed",
+    }
+}
+
+fn hash_of(game: &Game, player: i32) -> String {
+    format!("{:016x}", game.player(player).grid.board_hash())
+}
+
+/// Counts what a batch run did and formats its output lines.
+#[derive(Debug, Default)]
+pub struct BatchLog {
+    commands: u32,
+    invalid: u32,
+    errors: u32,
+}
+
+impl BatchLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The line for a command `player` ran: `Ok(outcome)` or `Err(message)`.
+    pub fn record(&mut self, game: &Game, player: i32, command: &str, repeat: i32, result: Result<(&str, Option<String>), String>) -> String {
+        let (outcome, message) = match result {
+            Ok((outcome, message)) => (outcome.to_string(), message),
+            Err(e) => {
+                self.errors += 1;
+                ("error".to_string(), Some(e))
+            }
+        };
+        self.line(game, player, command, repeat, outcome, message)
+    }
+
+    /// The line for a token that is not a valid command.
+    pub fn invalid(&mut self, game: &Game, player: i32, token: &str, message: String) -> String {
+        self.invalid += 1;
+        self.line(game, player, token, 1, "invalid".to_string(), Some(message))
+    }
+
+    fn line(&mut self, game: &Game, player: i32, command: &str, repeat: i32, outcome: String, message: Option<String>) -> String {
+        self.commands += 1;
+        let record = CommandRecord {
+            command: command.to_string(),
+            repeat,
+            player,
+            outcome,
+            message,
+            score: game.player(player).grid.cur_score(),
+            board_hash: hash_of(game, player),
+        };
+        serde_json::to_string(&Line::Command(&record)).unwrap_or_default()
+    }
+
+    pub fn invalid_count(&self) -> u32 {
+        self.invalid
+    }
+
+    pub fn summary(&self, game: &Game) -> Summary {
+        Summary {
+            commands: self.commands,
+            invalid: self.invalid,
+            errors: self.errors,
+            running: game.running,
+            loser: game.loser,
+            scores: [game.p1.grid.cur_score(), game.p2.grid.cur_score()],
+            hi_score: game.system_hi,
+            board_hashes: [hash_of(game, 1), hash_of(game, 2)],
+        }
+    }
+
+    pub fn summary_line(&self, game: &Game) -> String {
+        serde_json::to_string(&Line::Summary(&self.summary(game))).unwrap_or_default()
+    }
+}
@@ -0,0 +1,55 @@
+//! Reference client for the bot protocol (see `tetris::protocol`): plays the
+//! built-in heuristic bot over stdin/stdout. Usage: `protobot [easy|medium|hard]`.
+
+use std::io::{BufRead, Write};
+
+use tetris::block::BlockKind;
+use tetris::bot::{Bot, Difficulty};
+use tetris::protocol::{board_to_grid, BotMsg, EngineMsg};
+
+fn reply(out: &mut impl Write, msg: &BotMsg) {
+    let _ = writeln!(out, "{}", serde_json::to_string(msg).unwrap_or_default());
+    let _ = out.flush();
+}
+
+fn main() {
+    let difficulty = std::env::args().nth(1)
+        .and_then(|d| Difficulty::from_name(&d))
+        .unwrap_or(Difficulty::Medium);
+    let mut bot = Bot::new(difficulty, 12345);
+
+    let stdin = std::io::stdin();
+    let mut out = std::io::stdout();
+
+    for line in stdin.lock().lines() {
+        let Ok(line) = line else { break };
+        let msg: EngineMsg = match serde_json::from_str(&line) {
+            Ok(m) => m,
+            Err(e) => {
+                eprintln!("protobot: cannot parse '{}': {}", line, e);
+                continue;
+            }
+        };
+
+        match msg {
+            EngineMsg::Rules { .. } => {
+                reply(&mut out, &BotMsg::Ready { name: Some("protobot".to_string()) });
+            }
+            EngineMsg::Suggest { id, state, .. } => {
+                let moves = match board_to_grid(&state) {
+                    Some((grid, block)) => {
+                        let next = state.next.first().and_then(|&c| BlockKind::from_char(c));
+                        bot.plan_on_grid(&grid, &block, state.level_heavy_rows(), state.horizontal_extra(), next)
+                    }
+                    None => Vec::new(),
+                };
+                let moves = moves.iter().map(|m| m.command().to_string()).collect();
+                reply(&mut out, &BotMsg::Suggestion { id: Some(id), moves });
+            }
+            EngineMsg::Special { id, .. } => {
+                reply(&mut out, &BotMsg::Action { id: Some(id), action: "heavy".to_string(), block: None });
+            }
+            EngineMsg::Quit => break,
+        }
+    }
+}
@@ -0,0 +1,131 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use tetris::config::{parse_args, Config};
+use tetris::controller::ControllerSpec;
+use tetris::sim::{self, GameResult, SimOptions};
+
+struct SimArgs {
+    games: usize,
+    levels: Vec<i32>,
+    threads: usize,
+    p1: ControllerSpec,
+    p2: ControllerSpec,
+    max_pieces: u32,
+    out: String,
+    summary: String,
+}
+
+fn parse_sim_args(args: &[String], cfg: &Config) -> Result<SimArgs, String> {
+    let mut sa = SimArgs {
+        games: 100,
+        levels: vec![cfg.level],
+        threads: std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1),
+        p1: ControllerSpec::parse(cfg.controller1.as_deref().unwrap_or("random"))?,
+        p2: ControllerSpec::parse(cfg.controller2.as_deref().unwrap_or("random"))?,
+        max_pieces: 1000,
+        out: "sim_results.csv".to_string(),
+        summary: "sim_summary.csv".to_string(),
+    };
+    let mut i = 0;
+    while i < args.len() {
+        let val = args.get(i + 1);
+        match (args[i].as_str(), val) {
+            ("-games", Some(v)) => { sa.games = v.parse().map_err(|_| format!("bad -games: {}", v))?; i += 1; }
+            ("-threads", Some(v)) => { sa.threads = v.parse::<usize>().map_err(|_| format!("bad -threads: {}", v))?.max(1); i += 1; }
+            ("-maxpieces", Some(v)) => { sa.max_pieces = v.parse().map_err(|_| format!("bad -maxpieces: {}", v))?; i += 1; }
+            ("-out", Some(v)) => { sa.out = v.clone(); i += 1; }
+            ("-summary", Some(v)) => { sa.summary = v.clone(); i += 1; }
+            ("-levels", Some(v)) => {
+                sa.levels = v.split(',')
+                    .map(|s| s.trim().parse::<i32>().map_err(|_| format!("bad -levels: {}", v)))
+                    .collect::<Result<_, _>>()?;
+                i += 1;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    Ok(sa)
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let cfg = match parse_args(&args) {
+        Ok(cfg) => cfg,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        }
+    };
+    let sa = match parse_sim_args(&args, &cfg) {
+        Ok(sa) => sa,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        }
+    };
+    let base_seed = cfg.seed.unwrap_or(12345);
+    let level_set = match cfg.level_set() {
+        Ok(l) => l,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        }
+    };
+
+    let timeout = Duration::from_millis(cfg.bot_timeout_ms);
+    // Every (level, game) pair is one job; game i of each level uses seed base+i.
+    let jobs: Vec<SimOptions> = sa.levels.iter()
+        .flat_map(|&level| (0..sa.games).map(move |g| (level, g)))
+        .map(|(level, g)| SimOptions {
+            seed: base_seed.wrapping_add(g as u64),
+            level,
+            script1: cfg.script_file1.clone(),
+            script2: cfg.script_file2.clone(),
+            max_pieces: sa.max_pieces,
+            levels: level_set.clone(),
+        })
+        .collect();
+
+    let next = AtomicUsize::new(0);
+    let results: Mutex<Vec<GameResult>> = Mutex::new(Vec::with_capacity(jobs.len()));
+
+    std::thread::scope(|scope| {
+        for _ in 0..sa.threads.min(jobs.len().max(1)) {
+            scope.spawn(|| loop {
+                let idx = next.fetch_add(1, Ordering::Relaxed);
+                let Some(opts) = jobs.get(idx) else { break };
+
+                // controllers are seeded from the game seed so runs are reproducible
+                let controllers = sa.p1.build(opts.seed.wrapping_mul(2), timeout)
+                    .and_then(|c1| Ok((c1, sa.p2.build(opts.seed.wrapping_mul(2) + 1, timeout)?)));
+                let (mut c1, mut c2) = match controllers {
+                    Ok(c) => c,
+                    Err(e) => {
+                        eprintln!("game seed {} level {}: {}", opts.seed, opts.level, e);
+                        continue;
+                    }
+                };
+                match sim::play_game(opts, c1.as_mut(), c2.as_mut()) {
+                    Ok(r) => results.lock().unwrap().push(r),
+                    Err(e) => eprintln!("game seed {} level {}: {}", opts.seed, opts.level, e),
+                }
+            });
+        }
+    });
+
+    let mut results = results.into_inner().unwrap();
+    results.sort_by_key(|r| (r.level, r.seed));
+
+    if let Err(e) = std::fs::write(&sa.out, sim::results_csv(&results)) {
+        eprintln!("Cannot write {}: {}", sa.out, e);
+    }
+    let summary = sim::summary_csv(&results);
+    if let Err(e) = std::fs::write(&sa.summary, &summary) {
+        eprintln!("Cannot write {}: {}", sa.summary, e);
+    }
+    println!("Played {} games.", results.len());
+    print!("{}", summary);
+}
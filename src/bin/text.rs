@@ -1,8 +1,20 @@
+use std::collections::VecDeque;
+
+use tetris::batch::{self, BatchLog};
 use tetris::commands::{CommandTable, TokenStream};
 use tetris::config::parse_args;
-use tetris::render_text::print_two_boards;
+use tetris::render_text::{print_snapshot, print_two_boards};
 use tetris::game::Game;
 use tetris::block::{BlockKind};
+use tetris::config::Config;
+use tetris::controller::Controller;
+use tetris::net::{Client, Server, ServerMsg};
+use tetris::finesse;
+use tetris::player::{Move, MoveOutcome};
+use tetris::repl;
+use tetris::script::Script;
+use tetris::sim;
+use tetris::stats;
 
 fn next_non_newline(ts: &mut TokenStream, pending: &mut Vec<String>) -> Option<String> {
     loop {
@@ -14,13 +26,59 @@ fn next_non_newline(ts: &mut TokenStream, pending: &mut Vec<String>) -> Option<S
 
 fn main() {
     let args: Vec<String> = std::env::args().skip(1).collect();
-    let cfg = parse_args(&args);
+    let cfg = match parse_args(&args) {
+        Ok(cfg) => cfg,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        }
+    };
+
+    if let Some(addr) = cfg.connect.as_deref() {
+        run_client(addr, &cfg);
+        return;
+    }
 
     let mut table = CommandTable::new();
-    let mut stream = TokenStream::new();
+    let stream = match (cfg.batch, cfg.batch_file.as_deref()) {
+        (true, Some(file)) => match TokenStream::from_file(file) {
+            Ok(s) => s,
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(2);
+            }
+        },
+        (true, None) => TokenStream::new(),
+        (false, _) => {
+            let mut s = TokenStream::new();
+            s.use_line_editor(repl::default_history_path());
+            s
+        }
+    };
+    let mut batch = cfg.batch.then(BatchLog::new);
+    // inputs[0] is the shared input; -input1/-input2 give a player their own
+    let mut inputs = vec![Input::new(stream)];
+    let mut seat = [0usize; 2];
+    for (i, path) in [&cfg.input1, &cfg.input2].into_iter().enumerate() {
+        let Some(path) = path else { continue };
+        match TokenStream::from_reader(path) {
+            Ok(s) => {
+                inputs.push(Input::new(s));
+                seat[i] = inputs.len() - 1;
+            }
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(2);
+            }
+        }
+    }
+    let mut active = 0;
+    // lines qualified for a player (`p2:drop`) while it was the other's turn
+    let mut held: [VecDeque<Vec<String>>; 2] = Default::default();
+    load_rc(&cfg, &mut table);
 
     // Create game
-    let mut game = match Game::new(cfg.seed, cfg.level, cfg.script_file1.clone(), cfg.script_file2.clone()) {
+    let mut game = match cfg.new_game() {
         Ok(g) => g,
         Err(e) => {
             eprintln!("Init error: {}", e);
@@ -28,23 +86,114 @@ fn main() {
         }
     };
 
+    let mut controllers = match cfg.build_controllers() {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("{}", e);
+            return;
+        }
+    };
+
+    if let Some(addr) = cfg.serve.as_deref() {
+        serve(addr, &mut game, &mut controllers, &cfg);
+        return;
+    }
+
     // Initial draw
-    print_two_boards(&game.p1.grid, &game.p2.grid, game.p1.has_blind(), game.p2.has_blind(), game.system_hi);
-    println!("Current player: {}", game.current_player);
+    if batch.is_none() {
+        show(&game);
+    }
 
     while game.running {
-        let token = match next_non_newline(&mut stream, &mut table.pending) {
+        let cp = game.current_player;
+        if let Some(ctrl) = controllers[(cp - 1) as usize].as_mut() {
+            sim::play_turn(&mut game, cp, ctrl.as_mut());
+            match &mut batch {
+                Some(log) => println!("{}", log.record(&game, cp, "computer", 1, Ok(("ok", None)))),
+                None => {
+                    println!("Player {} (computer) played.", cp);
+                    show(&game);
+                }
+            }
+            continue;
+        }
+
+        // each input keeps its own unfinished macros and loops
+        let seat_idx = seat[(cp - 1) as usize];
+        if seat_idx != active {
+            std::mem::swap(&mut table.pending, &mut inputs[active].pending);
+            std::mem::swap(&mut table.pending, &mut inputs[seat_idx].pending);
+            active = seat_idx;
+        }
+        let Input { stream, script, .. } = &mut inputs[active];
+
+        if table.pending.is_empty() {
+            table.reset_expansions();
+            stream.set_completions(&table.names);
+            if let Some(line) = held[(cp - 1) as usize].pop_front() {
+                table.pending.extend(line.into_iter().rev());
+            }
+        }
+        let from_pending = !table.pending.is_empty();
+        let mut token = match next_non_newline(stream, &mut table.pending) {
             Some(t) => t,
-            None => break,
+            // the player on turn has nothing more to play, so the game cannot go on
+            None => {
+                if active != 0 {
+                    eprintln!("Player {}'s input ended.", cp);
+                }
+                break;
+            }
+        };
+
+        if let Some((player, rest)) = split_qualifier(&token) {
+            if !(1..=2).contains(&player) || rest.is_empty() {
+                reject(&mut batch, &game, &token, format!("Invalid player qualifier: {}", token));
+                continue;
+            }
+            if controllers[(player - 1) as usize].is_some() {
+                reject(&mut batch, &game, &token, format!("Player {} is played by the computer", player));
+                continue;
+            }
+            if player != cp {
+                // hold it, with the rest of its line, until that player's turn
+                let mut line = vec![rest.to_string()];
+                if !from_pending {
+                    while let Some(t) = stream.next_token(&mut table.pending) {
+                        if t == "\n" { break; }
+                        line.push(t);
+                    }
+                }
+                line.push("\n".to_string());
+                held[(player - 1) as usize].push_back(line);
+                continue;
+            }
+            token = rest.to_string();
+        }
+
+        if Script::is_keyword(&token) {
+            if let Err(e) = script.run(&token, &game, stream, &mut table.pending) {
+                reject(&mut batch, &game, &token, e);
+            }
+            continue;
+        }
+        let token = match script.substitute(&token) {
+            Ok(t) => t,
+            Err(e) => {
+                reject(&mut batch, &game, &token, e);
+                continue;
+            }
         };
 
-        let (mut repeat, command) = match table.parse_command_token(&token) {
+        let (repeat, command) = match table.parse_command_token(&token) {
             Ok(x) => x,
             Err(msg) => {
-                if !msg.is_empty() { eprintln!("{}", msg); }
-                print_two_boards(&game.p1.grid, &game.p2.grid, game.p1.has_blind(), game.p2.has_blind(), game.system_hi);
-                if game.running {
-                    println!("Current player: {}", game.current_player);
+                if msg.is_empty() {
+                    continue;
+                }
+                reject(&mut batch, &game, &token, msg);
+                if batch.is_none() {
+                    show(&game);
                 }
                 continue;
             }
@@ -52,390 +201,414 @@ fn main() {
 
         // Macro expansion if needed
         if table.is_macro_name(&command) {
-            if let Some(seq) = table.macro_seq(&command) {
-                if seq.is_empty() {
-                    eprintln!("macro '{}' has empty body; ignoring.", command);
-                } else {
-                    for _ in 0..repeat {
-                        for t in seq.iter().rev() {
-                            table.pending.push(t.clone());
-                        }
-                    }
+            if let Err(e) = push_macro(&command, repeat, stream, &mut table) {
+                // an expansion limit empties the pending stack, loop ends included
+                if table.pending.is_empty() {
+                    script.drop_loops();
+                }
+                match &mut batch {
+                    Some(log) => println!("{}", log.record(&game, game.current_player, &command, repeat, Err(e))),
+                    None => eprintln!("{}", e),
                 }
             }
             continue;
         }
 
         let current_player = game.current_player;
-        let heavy = if current_player == 1 { game.p1.level.is_heavy() } else { game.p2.level.is_heavy() };
-
-        // Helper closure to access current player's mutable state safely
-        // We'll do it with matches inside each command to avoid borrow conflicts.
 
-        match command.as_str() {
+        // what the command did: an outcome and message for batch output, or an error
+        let result: Result<(&str, Option<String>), String> = match command.as_str() {
             "quit" => break,
 
             "sequence" => {
                 // ignore repeat (already forced to 1)
-                let Some(file) = next_non_newline(&mut stream, &mut table.pending) else {
-                    eprintln!("Missing file name for sequence");
-                    continue;
-                };
-                if let Err(e) = stream.push_file(&file) {
-                    eprintln!("{}", e);
+                match next_non_newline(stream, &mut table.pending) {
+                    Some(file) => stream.push_file(&file).map(|_| ("ok", None)),
+                    None => Err("Missing file name for sequence".to_string()),
                 }
             }
 
             "norandom" => {
-                let Some(file) = next_non_newline(&mut stream, &mut table.pending) else {
-                    eprintln!("Missing file name for norandom");
-                    continue;
-                };
-
-                let lvl = if current_player == 1 { game.p1.level.number() } else { game.p2.level.number() };
-                if lvl < 3 {
-                    eprintln!("norandom is only relevant in levels 3 and 4");
-                } else {
-                    let res = if current_player == 1 { game.p1.level.load_sequence(&file).and_then(|_| game.p1.level.set_random(false)) }
-                              else { game.p2.level.load_sequence(&file).and_then(|_| game.p2.level.set_random(false)) };
-                    if let Err(e) = res { eprintln!("norandom error: {}", e); }
+                match next_non_newline(stream, &mut table.pending) {
+                    None => Err("Missing file name for norandom".to_string()),
+                    Some(file) => {
+                        let level = &mut game.player_mut(current_player).level;
+                        let res = level.load_sequence(&file).and_then(|_| level.set_random(false));
+                        res.map(|_| ("ok", None)).map_err(|e| format!("norandom error: {}", e))
+                    }
                 }
             }
 
             "random" => {
-                let lvl = if current_player == 1 { game.p1.level.number() } else { game.p2.level.number() };
-                if lvl < 3 {
-                    eprintln!("random is only relevant in levels 3 and 4");
-                } else {
-                    let res = if current_player == 1 { game.p1.level.set_random(true) } else { game.p2.level.set_random(true) };
-                    if let Err(e) = res { eprintln!("{}", e); }
-                }
+                game.player_mut(current_player).level.set_random(true).map(|_| ("ok", None))
             }
 
             // Force current block to a specific type (I/J/L/S/T/O/Z)
             "I" | "J" | "L" | "S" | "T" | "O" | "Z" => {
+                let mut res = Ok(("ok", None));
                 for _ in 0..repeat {
                     let kind = BlockKind::from_char(command.chars().next().unwrap()).unwrap();
-                    let res = if current_player == 1 { game.p1.force_replace_current(kind) } else { game.p2.force_replace_current(kind) };
-                    if let Err(e) = res {
-                        eprintln!("Force-block error: {}", e);
-                        println!("Game over, player {} lost.", current_player);
+                    let r = if current_player == 1 { game.p1.force_replace_current(kind) } else { game.p2.force_replace_current(kind) };
+                    if let Err(e) = r {
+                        if batch.is_none() {
+                            println!("Game over, player {} lost.", current_player);
+                        }
                         game.running = false;
+                        game.loser = Some(current_player);
+                        res = Err(format!("Force-block error: {}", e));
                         break;
                     }
                 }
+                res
             }
 
             "restart" => {
+                report_stats(&game, &cfg, batch.is_none());
+                held = Default::default();
                 if let Err(e) = game.restart() {
                     eprintln!("restart error: {}", e);
                     break;
                 }
+                Ok(("ok", None))
             }
 
-            "cw" => {
-                for _ in 0..repeat {
-                    if !game.running { break; }
-                    if current_player == 1 {
-                        game.p1.cur.rotate_cw(&mut game.p1.grid);
-                        if heavy {
-                            if !game.p1.cur.move_down(&mut game.p1.grid) {
-                                game.handle_landing(1);
-                                if game.running {
-                                    // heavy rotate can cause landing => post-drop
-                                    game.p1.on_drop_effects();
-                                    if game.running && game.p1.last_cleared >= 2 {
-                                        // ask action
-                                        handle_special_action(&mut game, &mut stream, &mut table, 1);
-                                    }
-                                    game.current_player = 2;
-                                }
-                                break;
-                            }
-                        }
-                    } else {
-                        game.p2.cur.rotate_cw(&mut game.p2.grid);
-                        if heavy {
-                            if !game.p2.cur.move_down(&mut game.p2.grid) {
-                                game.handle_landing(2);
-                                if game.running {
-                                    game.p2.on_drop_effects();
-                                    if game.running && game.p2.last_cleared >= 2 {
-                                        handle_special_action(&mut game, &mut stream, &mut table, 2);
-                                    }
-                                    game.current_player = 1;
-                                }
-                                break;
-                            }
-                        }
+            "left" | "right" | "down" | "cw" | "ccw" | "drop" => {
+                let mv = Move::from_command(&command).unwrap();
+                let outcome = game.apply_moves(current_player, mv, repeat);
+                script.note_move(current_player, outcome);
+                if outcome == MoveOutcome::Landed && game.running {
+                    if game.finish_drop(current_player) {
+                        handle_special_action(&mut game, stream, &mut table, current_player, batch.is_none());
                     }
+                    game.end_turn(current_player);
                 }
+                Ok((batch::outcome_name(outcome), None))
             }
 
-            "ccw" => {
-                for _ in 0..repeat {
-                    if !game.running { break; }
-                    if current_player == 1 {
-                        game.p1.cur.rotate_ccw(&mut game.p1.grid);
-                        if heavy {
-                            if !game.p1.cur.move_down(&mut game.p1.grid) {
-                                game.handle_landing(1);
-                                if game.running {
-                                    game.p1.on_drop_effects();
-                                    if game.running && game.p1.last_cleared >= 2 {
-                                        handle_special_action(&mut game, &mut stream, &mut table, 1);
-                                    }
-                                    game.current_player = 2;
-                                }
-                                break;
-                            }
-                        }
-                    } else {
-                        game.p2.cur.rotate_ccw(&mut game.p2.grid);
-                        if heavy {
-                            if !game.p2.cur.move_down(&mut game.p2.grid) {
-                                game.handle_landing(2);
-                                if game.running {
-                                    game.p2.on_drop_effects();
-                                    if game.running && game.p2.last_cleared >= 2 {
-                                        handle_special_action(&mut game, &mut stream, &mut table, 2);
-                                    }
-                                    game.current_player = 1;
-                                }
-                                break;
-                            }
-                        }
-                    }
-                }
+            "levelup" | "leveldown" => {
+                let step = if command == "levelup" { repeat } else { -repeat };
+                let lvl = game.player(current_player).level.number();
+                let target = lvl.saturating_add(step).clamp(0, game.levels.max_level());
+                game.set_level(current_player, target).map(|_| ("ok", None))
             }
 
-            "left" => {
-                for _ in 0..repeat {
-                    if !game.running { break; }
-
-                    let moved = if current_player == 1 { game.p1.cur.move_left(&mut game.p1.grid) } else { game.p2.cur.move_left(&mut game.p2.grid) };
-                    if !moved { break; }
-
-                    let extra = {
-                        let lvl_heavy = if current_player == 1 { game.p1.level.is_heavy() } else { game.p2.level.is_heavy() };
-                        let eff_heavy = if current_player == 1 { game.p1.extra_heavy_after_horizontal() } else { game.p2.extra_heavy_after_horizontal() };
-                        (if lvl_heavy { 1 } else { 0 }) + eff_heavy
-                    };
-
-                    if extra > 0 {
-                        let mut forced_drop = false;
-                        for _ in 0..extra {
-                            let ok = if current_player == 1 { game.p1.cur.move_down(&mut game.p1.grid) } else { game.p2.cur.move_down(&mut game.p2.grid) };
-                            if !ok {
-                                game.handle_landing(current_player);
-                                forced_drop = true;
-                                break;
-                            }
-                        }
-                        if forced_drop && game.running {
-                            if current_player == 1 {
-                                game.p1.on_drop_effects();
-                                if game.p1.last_cleared >= 2 { handle_special_action(&mut game, &mut stream, &mut table, 1); }
-                                game.current_player = 2;
-                            } else {
-                                game.p2.on_drop_effects();
-                                if game.p2.last_cleared >= 2 { handle_special_action(&mut game, &mut stream, &mut table, 2); }
-                                game.current_player = 1;
-                            }
-                            break;
-                        }
+            "rename" | "macro" | "unalias" | "unmacro" | "save-bindings" | "expand" | "help" | "describe" => {
+                let res = define_command(&command, stream, &mut table);
+                if batch.is_none() {
+                    if let Ok(text) = &res {
+                        print!("{}", text);
                     }
                 }
+                res.map(|text| ("ok", Some(text.trim_end().to_string())))
             }
 
-            "right" => {
-                for _ in 0..repeat {
-                    if !game.running { break; }
-
-                    let moved = if current_player == 1 { game.p1.cur.move_right(&mut game.p1.grid) } else { game.p2.cur.move_right(&mut game.p2.grid) };
-                    if !moved { break; }
-
-                    let extra = {
-                        let lvl_heavy = if current_player == 1 { game.p1.level.is_heavy() } else { game.p2.level.is_heavy() };
-                        let eff_heavy = if current_player == 1 { game.p1.extra_heavy_after_horizontal() } else { game.p2.extra_heavy_after_horizontal() };
-                        (if lvl_heavy { 1 } else { 0 }) + eff_heavy
-                    };
-
-                    if extra > 0 {
-                        let mut forced_drop = false;
-                        for _ in 0..extra {
-                            let ok = if current_player == 1 { game.p1.cur.move_down(&mut game.p1.grid) } else { game.p2.cur.move_down(&mut game.p2.grid) };
-                            if !ok {
-                                game.handle_landing(current_player);
-                                forced_drop = true;
-                                break;
-                            }
-                        }
-                        if forced_drop && game.running {
-                            if current_player == 1 {
-                                game.p1.on_drop_effects();
-                                if game.p1.last_cleared >= 2 { handle_special_action(&mut game, &mut stream, &mut table, 1); }
-                                game.current_player = 2;
-                            } else {
-                                game.p2.on_drop_effects();
-                                if game.p2.last_cleared >= 2 { handle_special_action(&mut game, &mut stream, &mut table, 2); }
-                                game.current_player = 1;
-                            }
-                            break;
-                        }
-                    }
+            _ => {
+                reject(&mut batch, &game, &command, "Invalid command".to_string());
+                if batch.is_none() {
+                    show(&game);
                 }
+                continue;
             }
+        };
 
-            "down" => {
-                for _ in 0..repeat {
-                    if !game.running { break; }
-
-                    if current_player == 1 {
-                        let ok1 = game.p1.cur.move_down(&mut game.p1.grid);
-                        if !ok1 {
-                            if heavy {
-                                game.handle_landing(1);
-                                if game.running {
-                                    game.p1.on_drop_effects();
-                                    if game.p1.last_cleared >= 2 { handle_special_action(&mut game, &mut stream, &mut table, 1); }
-                                    game.current_player = 2;
-                                }
-                                break;
-                            }
-                        } else if heavy {
-                            let ok2 = game.p1.cur.move_down(&mut game.p1.grid);
-                            if !ok2 {
-                                game.handle_landing(1);
-                                if game.running {
-                                    game.p1.on_drop_effects();
-                                    if game.p1.last_cleared >= 2 { handle_special_action(&mut game, &mut stream, &mut table, 1); }
-                                    game.current_player = 2;
-                                }
-                                break;
-                            }
-                        }
-                    } else {
-                        let ok1 = game.p2.cur.move_down(&mut game.p2.grid);
-                        if !ok1 {
-                            if heavy {
-                                game.handle_landing(2);
-                                if game.running {
-                                    game.p2.on_drop_effects();
-                                    if game.p2.last_cleared >= 2 { handle_special_action(&mut game, &mut stream, &mut table, 2); }
-                                    game.current_player = 1;
-                                }
-                                break;
-                            }
-                        } else if heavy {
-                            let ok2 = game.p2.cur.move_down(&mut game.p2.grid);
-                            if !ok2 {
-                                game.handle_landing(2);
-                                if game.running {
-                                    game.p2.on_drop_effects();
-                                    if game.p2.last_cleared >= 2 { handle_special_action(&mut game, &mut stream, &mut table, 2); }
-                                    game.current_player = 1;
-                                }
-                                break;
-                            }
-                        }
-                    }
-                }
+        if command == "restart" {
+            for input in &mut inputs {
+                input.script.reset();
             }
+        }
 
-            "drop" => {
-                // repeat is always 1 (multiplier ignored), but keep structure
-                if current_player == 1 {
-                    game.p1.cur.drop(&mut game.p1.grid);
-                    game.handle_landing(1);
-                    if game.running {
-                        game.p1.on_drop_effects();
-                        if game.p1.last_cleared >= 2 { handle_special_action(&mut game, &mut stream, &mut table, 1); }
-                        game.current_player = 2;
-                    }
-                } else {
-                    game.p2.cur.drop(&mut game.p2.grid);
-                    game.handle_landing(2);
-                    if game.running {
-                        game.p2.on_drop_effects();
-                        if game.p2.last_cleared >= 2 { handle_special_action(&mut game, &mut stream, &mut table, 2); }
-                        game.current_player = 1;
-                    }
+        match &mut batch {
+            Some(log) => println!("{}", log.record(&game, current_player, &command, repeat, result)),
+            None => {
+                if let Err(e) = result {
+                    eprintln!("{}", e);
                 }
+                show(&game);
             }
+        }
+    }
 
-            "leveldown" => {
-                for _ in 0..repeat {
-                    let lvl = if current_player == 1 { game.p1.level.number() } else { game.p2.level.number() };
-                    if lvl <= 0 { break; }
-                    let _ = game.set_level(current_player, lvl - 1);
-                }
+    report_stats(&game, &cfg, batch.is_none());
+    if let Some(log) = batch {
+        println!("{}", log.summary_line(&game));
+        if cfg.strict && log.invalid_count() > 0 {
+            std::process::exit(1);
+        }
+    }
+}
+
+/// A source of commands for one or both players.
+struct Input {
+    stream: TokenStream,
+    /// Macro and loop tokens still to run, parked here while another input is active.
+    pending: Vec<String>,
+    script: Script,
+}
+
+impl Input {
+    fn new(stream: TokenStream) -> Self {
+        Self { stream, pending: Vec::new(), script: Script::new() }
+    }
+}
+
+/// `p1:left` -> `(1, "left")`.
+fn split_qualifier(token: &str) -> Option<(i32, &str)> {
+    let (player, rest) = token.strip_prefix('p')?.split_once(':')?;
+    Some((player.parse().ok()?, rest))
+}
+
+/// Both boards and, while the game runs, whose turn it is.
+fn show(game: &Game) {
+    print_two_boards(&game.p1.grid, &game.p2.grid, game.p1.has_blind(), game.p2.has_blind(), game.system_hi);
+    if game.running {
+        println!("Current player: {}", game.current_player);
+    }
+}
+
+/// Reports a token that is not a command: on stderr, or as a batch line.
+fn reject(batch: &mut Option<BatchLog>, game: &Game, token: &str, msg: String) {
+    match batch {
+        Some(log) => println!("{}", log.invalid(game, game.current_player, token, msg)),
+        None => eprintln!("{}", msg),
+    }
+}
+
+/// Hosts a networked game; the terminal only shows joins and the final result.
+fn serve(addr: &str, game: &mut Game, controllers: &mut [Option<Box<dyn Controller>>; 2], cfg: &Config) {
+    let mut server = match Server::bind(addr) {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!("Cannot listen on {}: {}", addr, e);
+            return;
+        }
+    };
+    server.set_spectator_delay(cfg.spectate_delay);
+    let local = server.local_addr().map(|a| a.to_string()).unwrap_or_else(|_| addr.to_string());
+    println!("Waiting for players on {} ...", local);
+    if let Err(e) = server.run(game, controllers) {
+        eprintln!("server error: {}", e);
+    }
+    print_two_boards(&game.p1.grid, &game.p2.grid, game.p1.has_blind(), game.p2.has_blind(), game.system_hi);
+    report_stats(game, cfg, true);
+}
+
+/// Reads commands until one that is not a macro, alias or definition.
+fn next_command(stream: &mut TokenStream, table: &mut CommandTable) -> Option<(i32, String)> {
+    loop {
+        if table.pending.is_empty() {
+            table.reset_expansions();
+            stream.set_completions(&table.names);
+        }
+        let token = next_non_newline(stream, &mut table.pending)?;
+        let (repeat, command) = match table.parse_command_token(&token) {
+            Ok(x) => x,
+            Err(msg) => {
+                if !msg.is_empty() { eprintln!("{}", msg); }
+                continue;
             }
+        };
+        if table.is_macro_name(&command) {
+            if let Err(e) = push_macro(&command, repeat, stream, table) {
+                eprintln!("{}", e);
+            }
+            continue;
+        }
+        if matches!(command.as_str(), "rename" | "macro" | "unalias" | "unmacro" | "save-bindings" | "expand" | "help" | "describe") {
+            match define_command(&command, stream, table) {
+                Ok(text) => print!("{}", text),
+                Err(e) => eprintln!("{}", e),
+            }
+            continue;
+        }
+        return Some((repeat, command));
+    }
+}
 
-            "levelup" => {
-                for _ in 0..repeat {
-                    let lvl = if current_player == 1 { game.p1.level.number() } else { game.p2.level.number() };
-                    if lvl >= 4 { break; }
-                    let _ = game.set_level(current_player, lvl + 1);
-                }
+/// Loads aliases and macros from the rc file, if there is one.
+fn load_rc(cfg: &Config, table: &mut CommandTable) {
+    let Some(path) = cfg.rc_path() else { return };
+    match table.load_bindings(&path) {
+        Ok(errors) => {
+            for e in errors {
+                eprintln!("{}", e);
             }
+        }
+        Err(e) => eprintln!("{}", e),
+    }
+}
 
-            "rename" => {
-                let Some(new_name) = next_non_newline(&mut stream, &mut table.pending) else {
-                    eprintln!("Usage: rename <newname> <existingcommand>");
-                    continue;
-                };
-                let Some(old_name) = next_non_newline(&mut stream, &mut table.pending) else {
-                    eprintln!("Usage: rename <newname> <existingcommand>");
-                    continue;
-                };
+/// Queues `repeat` expansions of macro `name`, taking its arguments from the
+/// rest of the line.
+fn push_macro(name: &str, repeat: i32, stream: &mut TokenStream, table: &mut CommandTable) -> Result<(), String> {
+    let mut args = Vec::new();
+    while args.len() < table.macro_arity(name) {
+        match stream.next_token(&mut table.pending) {
+            Some(t) if t != "\n" => args.push(t),
+            _ => break,
+        }
+    }
+    let seq = table.expand_macro(name, &args)?;
+    table.push_expansion(name, &seq, repeat)
+}
 
-                match table.define_alias(&new_name, &old_name) {
-                    Ok(msg) => println!("{}", msg),
-                    Err(e) => eprintln!("{}", e),
-                }
-            }
+/// Plays (or watches) a networked game hosted with `-serve`.
+fn run_client(addr: &str, cfg: &Config) {
+    let mut client = match Client::connect(addr, cfg.name.as_deref(), cfg.spectate) {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("Cannot connect to {}: {}", addr, e);
+            return;
+        }
+    };
+    let me = client.seat;
+    if me == 0 {
+        println!("Connected to {} as a spectator.", addr);
+    } else {
+        println!("Connected to {} as player {}. Waiting for players...", addr, me);
+    }
 
-            "macro" => {
-                // macro <name> <rest-of-line...>
-                let Some(macro_name) = next_non_newline(&mut stream, &mut table.pending) else {
-                    eprintln!("Usage: macro <name> <sequence-of-commands>");
+    let mut table = CommandTable::new();
+    load_rc(cfg, &mut table);
+    let mut stream = TokenStream::new();
+    stream.use_line_editor(repl::default_history_path());
+
+    while let Some(msg) = client.recv() {
+        let sent = match msg {
+            ServerMsg::State { state } => {
+                print_snapshot(&state);
+                // the game over itself arrives as an event
+                if !state.running {
+                    return;
+                }
+                println!("Current player: {}", state.current_player);
+                if me == 0 || state.current_player != me || state.awaiting_special.is_some() {
                     continue;
-                };
-
-                // collect tokens until newline sentinel
-                let mut seq: Vec<String> = Vec::new();
+                }
                 loop {
-                    let t = stream.next_token(&mut table.pending);
-                    match t {
-                        None => break,
-                        Some(x) => {
-                            if x == "\n" { break; }
-                            seq.push(x);
-                        }
+                    let Some((repeat, command)) = next_command(&mut stream, &mut table) else { return };
+                    if command == "quit" { return; }
+                    match Move::from_command(&command) {
+                        Some(mv) => break client.send_move(mv, repeat),
+                        None => eprintln!("'{}' is not available in network play", command),
                     }
                 }
+            }
+            ServerMsg::ChooseSpecial { player } if player == me => {
+                println!("Player {}, choose special action (blind / heavy / force): ", me);
+                let Some(action) = next_non_newline(&mut stream, &mut table.pending) else { return };
+                let block = if action == "force" { next_non_newline(&mut stream, &mut table.pending) } else { None };
+                client.send_special(&action, block.as_deref())
+            }
+            ServerMsg::Event { event } => {
+                println!("* {}", event.describe());
+                continue;
+            }
+            ServerMsg::Error { message } => {
+                eprintln!("server: {}", message);
+                continue;
+            }
+            _ => continue,
+        };
+        if let Err(e) = sent {
+            eprintln!("Connection lost: {}", e);
+            return;
+        }
+    }
+    println!("Server closed the connection.");
+}
 
-                match table.define_macro(&macro_name, seq) {
-                    Ok(msg) => println!("{}", msg),
-                    Err(e) => eprintln!("{}", e),
-                }
+/// Handles `rename <new> <old>`, `macro <name> <commands...>`, `unalias <name>`,
+/// `unmacro <name>`, `save-bindings <file>`, `expand <name> <args...>`,
+/// `help [command]` and `describe <name>`, returning the text to show.
+fn define_command(command: &str, stream: &mut TokenStream, table: &mut CommandTable) -> Result<String, String> {
+    if command == "help" {
+        // the argument is optional, so only look at the rest of this line
+        return match stream.next_token(&mut table.pending) {
+            Some(t) if t != "\n" => table.help_for(&t).map_err(|e| format!("help: {}", e)),
+            Some(t) => {
+                table.pending.push(t);
+                Ok(table.help())
             }
+            None => Ok(table.help()),
+        };
+    }
 
-            _ => {
-                eprintln!("Invalid command");
+    if command == "describe" {
+        let name = next_non_newline(stream, &mut table.pending).ok_or("Usage: describe <name>")?;
+        return table.describe(&name);
+    }
+
+    if command == "expand" {
+        let name = next_non_newline(stream, &mut table.pending).ok_or("Usage: expand <macro> <arguments...>")?;
+        let mut args = Vec::new();
+        while args.len() < table.macro_arity(&name) {
+            match stream.next_token(&mut table.pending) {
+                Some(t) if t != "\n" => args.push(t),
+                _ => break,
             }
         }
+        if !table.is_macro_name(&name) {
+            return Err(format!("expand: '{}' is not a macro", name));
+        }
+        return table.expand(&name, &args).map(|tokens| format!("{}\n", tokens.join(" ")));
+    }
+
+    if matches!(command, "unalias" | "unmacro" | "save-bindings") {
+        let Some(arg) = next_non_newline(stream, &mut table.pending) else {
+            let what = if command == "save-bindings" { "file" } else { "name" };
+            return Err(format!("Usage: {} <{}>", command, what));
+        };
+        let res = match command {
+            "unalias" => table.remove_alias(&arg),
+            "unmacro" => table.remove_macro(&arg),
+            _ => table.save_bindings(&arg),
+        };
+        return res.map(|msg| format!("{}\n", msg));
+    }
 
-        print_two_boards(&game.p1.grid, &game.p2.grid, game.p1.has_blind(), game.p2.has_blind(), game.system_hi);
-        if game.running {
-            println!("Current player: {}", game.current_player);
+    if command == "rename" {
+        let usage = "Usage: rename <newname> <existingcommand>";
+        let new_name = next_non_newline(stream, &mut table.pending).ok_or(usage)?;
+        let old_name = next_non_newline(stream, &mut table.pending).ok_or(usage)?;
+        return table.define_alias(&new_name, &old_name).map(|msg| format!("{}\n", msg));
+    }
+
+    // macro <name> <rest-of-line...>
+    let macro_name = next_non_newline(stream, &mut table.pending).ok_or("Usage: macro <name> <sequence-of-commands>")?;
+
+    // collect tokens until newline sentinel
+    let mut seq: Vec<String> = Vec::new();
+    loop {
+        let t = stream.next_token(&mut table.pending);
+        match t {
+            None => break,
+            Some(x) => {
+                if x == "\n" { break; }
+                seq.push(x);
+            }
         }
     }
+
+    table.define_macro(&macro_name, seq).map(|msg| format!("{}\n", msg))
+}
+
+/// Prints the end-of-game report (unless `print` is false) and writes the stats files.
+fn report_stats(game: &Game, cfg: &Config, print: bool) {
+    let table = game.stats_table();
+    if print {
+        print!("{}", stats::format_report(&table));
+        print!("{}", finesse::format_report(1, &game.p1.finesse));
+        print!("{}", finesse::format_report(2, &game.p2.finesse));
+    }
+    if let Err(e) = stats::export(&table, cfg.stats_json.as_deref(), cfg.stats_csv.as_deref()) {
+        eprintln!("{}", e);
+    }
 }
 
-fn handle_special_action(game: &mut Game, stream: &mut TokenStream, table: &mut CommandTable, acting_player: i32) {
-    println!("Player {}, choose special action (blind / heavy / force): ", acting_player);
+fn handle_special_action(game: &mut Game, stream: &mut TokenStream, table: &mut CommandTable, acting_player: i32, prompt: bool) {
+    if prompt {
+        println!("Player {}, choose special action (blind / heavy / force): ", acting_player);
+    }
 
     let action = match next_non_newline(stream, &mut table.pending) {
         Some(a) => a,
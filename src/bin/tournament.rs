@@ -0,0 +1,113 @@
+use std::time::Duration;
+
+use tetris::config::parse_args;
+use tetris::net::Server;
+use tetris::tournament::{self, Format, MatchOptions, Ratings, Seat, Tournament};
+
+struct TourArgs {
+    roster: String,
+    format: Format,
+    games: u32,
+    max_pieces: u32,
+    ratings: String,
+    out: Option<String>,
+}
+
+fn parse_tour_args(args: &[String]) -> Result<TourArgs, String> {
+    let mut ta = TourArgs {
+        roster: "roster.txt".to_string(),
+        format: Format::RoundRobin,
+        games: 1,
+        max_pieces: 500,
+        ratings: "ratings.json".to_string(),
+        out: None,
+    };
+    let mut i = 0;
+    while i < args.len() {
+        let val = args.get(i + 1);
+        match (args[i].as_str(), val) {
+            ("-roster", Some(v)) => { ta.roster = v.clone(); i += 1; }
+            ("-format", Some(v)) => { ta.format = Format::from_name(v).ok_or_else(|| format!("bad -format: {} (roundrobin / elimination)", v))?; i += 1; }
+            ("-games", Some(v)) => { ta.games = v.parse().map_err(|_| format!("bad -games: {}", v))?; i += 1; }
+            ("-maxpieces", Some(v)) => { ta.max_pieces = v.parse().map_err(|_| format!("bad -maxpieces: {}", v))?; i += 1; }
+            ("-ratings", Some(v)) => { ta.ratings = v.clone(); i += 1; }
+            ("-out", Some(v)) => { ta.out = Some(v.clone()); i += 1; }
+            _ => {}
+        }
+        i += 1;
+    }
+    Ok(ta)
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let cfg = match parse_args(&args) {
+        Ok(cfg) => cfg,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        }
+    };
+    let ta = match parse_tour_args(&args) {
+        Ok(ta) => ta,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        }
+    };
+    let fail = |e: String| -> ! {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    };
+
+    let entrants = tournament::load_roster(&ta.roster).unwrap_or_else(|e| fail(e));
+    let mut ratings = Ratings::load(&ta.ratings).unwrap_or_else(|e| fail(e));
+
+    // humans play over the network
+    let mut server = None;
+    if entrants.iter().any(|e| matches!(e.seat, Seat::Human)) {
+        let addr = cfg.serve.clone().unwrap_or_else(|| tetris::net::with_default_port("0.0.0.0"));
+        let mut s = Server::bind(&addr).unwrap_or_else(|e| fail(format!("Cannot listen on {}: {}", addr, e)));
+        s.set_spectator_delay(cfg.spectate_delay);
+        server = Some(s);
+    }
+
+    let opts = MatchOptions {
+        base_seed: cfg.seed.unwrap_or(12345),
+        level: cfg.level,
+        script1: cfg.script_file1.clone(),
+        script2: cfg.script_file2.clone(),
+        max_pieces: ta.max_pieces,
+        games_per_match: ta.games,
+        bot_timeout: Duration::from_millis(cfg.bot_timeout_ms),
+    };
+    let mut t = Tournament { entrants, format: ta.format, opts, ratings: &mut ratings, results: Vec::new() };
+
+    let mut report = |t: &Tournament, r: &tournament::MatchResult| {
+        let [a, b] = r.players.map(|p| t.entrants[p].name.as_str());
+        let w = r.wins();
+        let s = r.total_scores();
+        let outcome = match r.winner() {
+            Some(p) => format!("{} wins", t.entrants[p].name),
+            None => "draw".to_string(),
+        };
+        println!("Round {}: {} vs {}  {}-{} (score {}-{})  {}", r.round + 1, a, b, w[0], w[1], s[0], s[1], outcome);
+    };
+    let champion = t.run(server.as_mut(), &mut report).unwrap_or_else(|e| fail(e));
+
+    let table = tournament::standings(t.entrants.len(), &t.results);
+    println!();
+    print!("{}", tournament::format_standings(&t.entrants, &table, t.ratings));
+    if let Some(c) = champion {
+        println!("Champion: {}", t.entrants[c].name);
+    }
+
+    if let Some(out) = &ta.out {
+        if let Err(e) = std::fs::write(out, tournament::results_csv(&t.entrants, &t.results)) {
+            eprintln!("Cannot write {}: {}", out, e);
+        }
+    }
+    if let Err(e) = t.ratings.save(&ta.ratings) {
+        eprintln!("{}", e);
+    }
+}
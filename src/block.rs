@@ -52,6 +52,11 @@ impl Block {
         Self { kind, id, cells: spawn_cells(kind) }
     }
 
+    /// A one-cell star block at the top of `column`.
+    pub fn star(id: i32, column: i32) -> Self {
+        Self { kind: BlockKind::Star, id, cells: vec![Cell { r: 7, c: column, ch: '*' }] }
+    }
+
     pub fn write_to_grid(&self, g: &mut Grid) {
         for cell in &self.cells {
             g.set_cell(cell.r as usize, cell.c as usize, cell.ch, self.id);
@@ -143,15 +148,15 @@ impl Block {
         while self.move_down(g) {}
     }
 
-    pub fn rotate_cw(&mut self, g: &mut Grid) {
-        self.rotate(g, true);
+    pub fn rotate_cw(&mut self, g: &mut Grid) -> bool {
+        self.rotate(g, true)
     }
 
-    pub fn rotate_ccw(&mut self, g: &mut Grid) {
-        self.rotate(g, false);
+    pub fn rotate_ccw(&mut self, g: &mut Grid) -> bool {
+        self.rotate(g, false)
     }
 
-    fn rotate(&mut self, g: &mut Grid, cw: bool) {
+    fn rotate(&mut self, g: &mut Grid, cw: bool) -> bool {
         // Clear current block from grid so collision checks don't see itself.
         self.clear_from_grid(g);
 
@@ -194,12 +199,12 @@ impl Block {
         let base_c = min_c;
 
         let mut new_cells: Vec<Cell> = Vec::new();
-        for r in 0..w {
-            for c in 0..h {
-                if rot[r][c] != ' ' {
+        for (r, row) in rot.iter().enumerate().take(w) {
+            for (c, &ch) in row.iter().enumerate().take(h) {
+                if ch != ' ' {
                     let nr = base_r - ((w - 1 - r) as i32);
                     let nc = base_c + (c as i32);
-                    new_cells.push(Cell { r: nr, c: nc, ch: rot[r][c] });
+                    new_cells.push(Cell { r: nr, c: nc, ch });
                 }
             }
         }
@@ -207,11 +212,12 @@ impl Block {
         if new_cells.len() != self.cells.len() || !Self::can_place(&new_cells, g, self.id) {
             // Revert
             self.write_to_grid(g);
-            return;
+            return false;
         }
 
         self.cells = new_cells;
         self.write_to_grid(g);
+        true
     }
 }
 
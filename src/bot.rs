@@ -0,0 +1,195 @@
+use std::collections::{HashMap, VecDeque};
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::block::{Block, BlockKind};
+use crate::controller::{Controller, SpecialAction};
+use crate::game::Game;
+use crate::grid::Grid;
+use crate::movegen::{placements_for, reachable_placements, Placement};
+use crate::player::{Move, PlayerState};
+
+// Feature weights (aggregate height, lines, holes, bumpiness) from the usual
+// four-feature Tetris evaluator; they hold up fine on the 11-wide board.
+const W_HEIGHT: f64 = -0.510066;
+const W_LINES: f64 = 0.760666;
+const W_HOLES: f64 = -0.35663;
+const W_BUMPINESS: f64 = -0.184483;
+
+/// How many of the best placements Easy picks from at random.
+const EASY_POOL: usize = 4;
+/// How many of the best one-block placements Hard looks ahead from.
+const HARD_BEAM: usize = 8;
+
+const FORCE_KINDS: [BlockKind; 7] = [
+    BlockKind::S, BlockKind::Z, BlockKind::O, BlockKind::T, BlockKind::L, BlockKind::J, BlockKind::I,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Difficulty {
+    /// Random pick among the few best placements.
+    Easy,
+    /// Best placement for the current block.
+    Medium,
+    /// Best placement looking one block ahead at the preview.
+    Hard,
+}
+
+impl Difficulty {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "easy" => Some(Difficulty::Easy),
+            "medium" => Some(Difficulty::Medium),
+            "hard" => Some(Difficulty::Hard),
+            _ => None,
+        }
+    }
+}
+
+pub struct Bot {
+    pub difficulty: Difficulty,
+    rng: StdRng,
+}
+
+impl Bot {
+    pub fn new(difficulty: Difficulty, seed: u64) -> Self {
+        Self { difficulty, rng: StdRng::seed_from_u64(seed) }
+    }
+
+    /// Picks the inputs for the current block of `p`, ending with the move that locks it.
+    pub fn choose_placement(&mut self, p: &PlayerState) -> Vec<Move> {
+        self.plan_on_grid(&p.grid, &p.cur, p.level.heavy_rows(), p.extra_drop_after_horizontal(), Some(p.next_kind))
+    }
+
+    /// Same as `choose_placement` for a bare board: `grid` must contain `block`;
+    /// `next` is the previewed block, if known (used by Hard).
+    pub fn plan_on_grid(&mut self, grid: &Grid, block: &Block, heavy: i32, horizontal_extra: i32,
+                        next: Option<BlockKind>) -> Vec<Move> {
+        let mut scored: Vec<(f64, Grid, Vec<Move>)> = placements_for(grid, block, heavy, horizontal_extra)
+            .into_iter()
+            .map(|pl| {
+                let locked = locked_grid(grid, block, &pl);
+                (evaluate(&locked), locked, pl.moves)
+            })
+            .collect();
+        if scored.is_empty() {
+            return vec![Move::Drop];
+        }
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+        if let (Difficulty::Hard, Some(next_kind)) = (self.difficulty, next) {
+            // look ahead only from the most promising placements
+            scored.truncate(HARD_BEAM);
+            for (score, locked, _) in &mut scored {
+                *score += best_followup(locked, next_kind, block.id + 1, heavy);
+            }
+            scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+        }
+
+        let pick = match self.difficulty {
+            Difficulty::Easy => self.rng.gen_range(0..scored.len().min(EASY_POOL)),
+            _ => 0,
+        };
+        scored.swap_remove(pick).2
+    }
+
+    /// Picks a special action against `opponent`.
+    pub fn choose_special(&mut self, opponent: &PlayerState) -> SpecialAction {
+        match self.difficulty {
+            Difficulty::Easy => match self.rng.gen_range(0..3) {
+                0 => SpecialAction::Blind,
+                1 => SpecialAction::Heavy,
+                _ => SpecialAction::Force(FORCE_KINDS[self.rng.gen_range(0..FORCE_KINDS.len())]),
+            },
+            Difficulty::Medium => SpecialAction::Heavy,
+            Difficulty::Hard => {
+                // force whichever block leaves the opponent with the worst best placement
+                let mut worst: Option<(f64, BlockKind)> = None;
+                for kind in FORCE_KINDS {
+                    let mut o = opponent.clone();
+                    if o.force_replace_current(kind).is_err() {
+                        return SpecialAction::Force(kind);
+                    }
+                    let best = reachable_placements(&o).iter()
+                        .map(|pl| evaluate(&locked_grid(&o.grid, &o.cur, pl)))
+                        .fold(f64::NEG_INFINITY, f64::max);
+                    if worst.map(|(w, _)| best < w).unwrap_or(true) {
+                        worst = Some((best, kind));
+                    }
+                }
+                worst.map(|(_, k)| SpecialAction::Force(k)).unwrap_or(SpecialAction::Heavy)
+            }
+        }
+    }
+}
+
+/// `grid` with its falling block `cur` moved to where `pl` locks it.
+fn locked_grid(grid: &Grid, cur: &Block, pl: &Placement) -> Grid {
+    let mut g = grid.clone();
+    cur.clear_from_grid(&mut g);
+    pl.block.write_to_grid(&mut g);
+    g
+}
+
+/// Heuristic value of a board with the last block set, after its rows are cleared.
+fn evaluate(locked: &Grid) -> f64 {
+    let mut grid = locked.clone();
+    let lines = grid.check_and_clear(&mut HashMap::new());
+    let heights = grid.column_heights();
+    let aggregate: i32 = heights.iter().sum();
+    let bumpiness: i32 = heights.windows(2).map(|w| (w[0] - w[1]).abs()).sum();
+    let holes = grid.count_holes();
+
+    W_HEIGHT * aggregate as f64
+        + W_LINES * lines as f64
+        + W_HOLES * holes as f64
+        + W_BUMPINESS * bumpiness as f64
+}
+
+/// Best evaluation for the previewed block once `locked` settles; a top-out scores -inf.
+/// Heavy effects expire on the drop, so only the level's own heaviness applies.
+fn best_followup(locked: &Grid, next_kind: BlockKind, next_id: i32, heavy: i32) -> f64 {
+    let mut grid = locked.clone();
+    grid.check_and_clear(&mut HashMap::new());
+
+    let block = Block::new(next_kind, next_id);
+    if !block.can_spawn(&grid) {
+        return f64::NEG_INFINITY;
+    }
+    block.write_to_grid(&mut grid);
+
+    placements_for(&grid, &block, heavy, heavy).iter()
+        .map(|pl| evaluate(&locked_grid(&grid, &block, pl)))
+        .fold(f64::NEG_INFINITY, f64::max)
+}
+
+/// Plays through `Bot`, planning a full placement whenever a new block appears.
+pub struct BotController {
+    bot: Bot,
+    plan: VecDeque<Move>,
+    planned_block: i32,
+}
+
+impl BotController {
+    pub fn new(difficulty: Difficulty, seed: u64) -> Self {
+        Self { bot: Bot::new(difficulty, seed), plan: VecDeque::new(), planned_block: 0 }
+    }
+}
+
+impl Controller for BotController {
+    fn next_move(&mut self, game: &Game, player: i32) -> Move {
+        let p = game.player(player);
+        // block ids change on every spawn and on a forced replacement
+        if p.cur.id != self.planned_block || self.plan.is_empty() {
+            self.plan = self.bot.choose_placement(p).into();
+            self.planned_block = p.cur.id;
+        }
+        self.plan.pop_front().unwrap_or(Move::Drop)
+    }
+
+    fn choose_special(&mut self, game: &Game, player: i32) -> SpecialAction {
+        let opponent = game.player(if player == 1 { 2 } else { 1 });
+        self.bot.choose_special(opponent)
+    }
+}
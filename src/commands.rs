@@ -1,32 +1,122 @@
-use std::collections::{HashMap, VecDeque};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::PathBuf;
+
+use crate::repl::LineEditor;
+
+/// Nesting limit for macro expansions started by one input token.
+pub const MAX_EXPANSION_DEPTH: usize = 32;
+/// Tokens that macros started by one input token may expand to in total.
+pub const MAX_EXPANSION_TOKENS: usize = 100_000;
 
 #[derive(Clone, Debug)]
 pub struct Binding {
     pub is_macro: bool,
     pub single: String,      // canonical command for normal commands/aliases
     pub seq: Vec<String>,    // macro body tokens
+    pub arity: usize,        // macro parameters $1..$arity
 }
 
 pub struct CommandTable {
     pub names: Vec<String>,
     pub map: HashMap<String, Binding>,
     pub pending: Vec<String>,            // macro-expanded tokens stack (LIFO)
+    expansions: Expansions,
+}
+
+/// Macro expansions in progress on a pending stack, checked against the limits above.
+#[derive(Default)]
+struct Expansions {
+    /// Stack height under each expansion that is still being consumed.
+    bases: Vec<usize>,
+    tokens: usize,
+}
+
+impl Expansions {
+    fn push(&mut self, pending: &mut Vec<String>, name: &str, seq: &[String], repeat: i32) -> Result<(), String> {
+        // an expansion is finished once the stack is back down to where it started
+        while self.bases.last().is_some_and(|&b| pending.len() <= b) {
+            self.bases.pop();
+        }
+        let repeat = repeat.max(1) as usize;
+        self.tokens = self.tokens.saturating_add(seq.len().saturating_mul(repeat));
+        let exceeded = if self.bases.len() >= MAX_EXPANSION_DEPTH {
+            format!("nests deeper than {} levels", MAX_EXPANSION_DEPTH)
+        } else if self.tokens > MAX_EXPANSION_TOKENS {
+            format!("expands to more than {} tokens", MAX_EXPANSION_TOKENS)
+        } else {
+            self.bases.push(pending.len());
+            for _ in 0..repeat {
+                pending.extend(seq.iter().rev().cloned());
+            }
+            return Ok(());
+        };
+        pending.clear();
+        self.bases.clear();
+        Err(format!("macro '{}' {}; expansion stopped", name, exceeded))
+    }
+}
+
+/// A built-in command with its help text.
+pub struct Builtin {
+    pub name: &'static str,
+    pub usage: &'static str,
+    pub description: &'static str,
+}
+
+const fn builtin(name: &'static str, usage: &'static str, description: &'static str) -> Builtin {
+    Builtin { name, usage, description }
+}
+
+/// Built-in commands, in `help` order. `[n]` marks commands that take a multiplier.
+pub const BUILTINS: &[Builtin] = &[
+    builtin("left", "[n]left", "move the block one column left"),
+    builtin("right", "[n]right", "move the block one column right"),
+    builtin("down", "[n]down", "move the block one row down"),
+    builtin("cw", "[n]cw", "rotate the block clockwise"),
+    builtin("ccw", "[n]ccw", "rotate the block counterclockwise"),
+    builtin("drop", "drop", "drop the block and end the turn"),
+    builtin("levelup", "[n]levelup", "raise the current player's level"),
+    builtin("leveldown", "[n]leveldown", "lower the current player's level"),
+    builtin("sequence", "sequence <file>", "run the commands in a file"),
+    builtin("restart", "restart", "start a new game (aliases and macros are kept)"),
+    builtin("random", "random", "random levels: random blocks again"),
+    builtin("norandom", "norandom <file>", "random levels: take blocks from a sequence file"),
+    builtin("quit", "quit", "end the game"),
+    builtin("I", "[n]I", "replace the current block with an I block"),
+    builtin("J", "[n]J", "replace the current block with a J block"),
+    builtin("L", "[n]L", "replace the current block with an L block"),
+    builtin("S", "[n]S", "replace the current block with an S block"),
+    builtin("T", "[n]T", "replace the current block with a T block"),
+    builtin("O", "[n]O", "replace the current block with an O block"),
+    builtin("Z", "[n]Z", "replace the current block with a Z block"),
+    builtin("rename", "rename <new> <command>", "add another name for a command"),
+    builtin("macro", "macro <name> <commands...>", "define a command that runs the rest of the line ($1, $2... are arguments)"),
+    builtin("unalias", "unalias <name>", "delete an alias"),
+    builtin("unmacro", "unmacro <name>", "delete a macro"),
+    builtin("save-bindings", "save-bindings <file>", "write all aliases and macros to a file"),
+    builtin("expand", "expand <macro> [args...]", "show the commands a macro runs, without running them"),
+    builtin("help", "help [command]", "list commands, or explain one"),
+    builtin("describe", "describe <name>", "show what an alias or macro stands for"),
+];
+
+impl Default for CommandTable {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl CommandTable {
     pub fn new() -> Self {
-        let names = vec![
-            "left","right","down","cw","ccw","drop",
-            "levelup","leveldown","sequence","restart","random","norandom",
-            "quit","I","J","L","S","T","O","Z","rename","macro"
-        ].into_iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        let names = BUILTINS.iter().map(|b| b.name.to_string()).collect::<Vec<_>>();
 
         let mut map = HashMap::new();
         for n in &names {
-            map.insert(n.clone(), Binding { is_macro: false, single: n.clone(), seq: Vec::new() });
+            map.insert(n.clone(), Binding { is_macro: false, single: n.clone(), seq: Vec::new(), arity: 0 });
         }
 
-        Self { names, map, pending: Vec::new() }
+        Self { names, map, pending: Vec::new(), expansions: Expansions::default() }
     }
 
     pub fn resolve_stem(&self, stem: &str) -> Result<String, String> {
@@ -61,6 +151,7 @@ impl CommandTable {
         let mut i = 0;
         while i < n && chars[i].is_ascii_digit() { i += 1; }
         let prefix_num: String = chars[..i].iter().collect();
+        if i == n { return Err(format!("Invalid command: {}", token)); }
 
         let mut j: i32 = (n as i32) - 1;
         while j >= 0 && chars[j as usize].is_ascii_digit() { j -= 1; }
@@ -90,6 +181,7 @@ impl CommandTable {
 
         let multiplier_not_allowed = matches!(command_out.as_str(),
             "drop" | "restart" | "quit" | "sequence" | "random" | "norandom" | "macro"
+            | "unalias" | "unmacro" | "save-bindings" | "expand" | "help" | "describe"
         );
 
         if multiplier_not_allowed && repeat != 1 {
@@ -109,7 +201,7 @@ impl CommandTable {
             return Err(format!("rename error: '{}' is not a valid base command", old_name));
         }
         let canonical = if old.single.is_empty() { old_name.to_string() } else { old.single.clone() };
-        self.map.insert(new_name.to_string(), Binding { is_macro: false, single: canonical.clone(), seq: Vec::new() });
+        self.map.insert(new_name.to_string(), Binding { is_macro: false, single: canonical.clone(), seq: Vec::new(), arity: 0 });
         self.names.push(new_name.to_string());
         Ok(format!("Alias created: '{}' → '{}'", new_name, canonical))
     }
@@ -121,9 +213,95 @@ impl CommandTable {
         if seq.is_empty() {
             return Err(format!("macro error: empty body for '{}'", name));
         }
-        self.map.insert(name.to_string(), Binding { is_macro: true, single: String::new(), seq });
+        let mut arity = 0;
+        let mut zero = false;
+        for t in &seq {
+            replace_params(t, |n| {
+                zero |= n == 0;
+                arity = arity.max(n);
+                String::new()
+            });
+        }
+        if zero {
+            return Err(format!("macro error: parameters of '{}' are numbered from $1", name));
+        }
+        self.map.insert(name.to_string(), Binding { is_macro: true, single: String::new(), seq, arity });
         self.names.push(name.to_string());
-        Ok(format!("Macro '{}' defined.", name))
+        if let Some(cycle) = self.find_cycle(name) {
+            self.map.remove(name);
+            self.names.pop();
+            return Err(format!("macro error: '{}' would call itself ({})", name, cycle.join(" → ")));
+        }
+        if arity > 0 {
+            Ok(format!("Macro '{}' defined with {} parameter(s).", name, arity))
+        } else {
+            Ok(format!("Macro '{}' defined.", name))
+        }
+    }
+
+    pub fn remove_alias(&mut self, name: &str) -> Result<String, String> {
+        match self.map.get(name) {
+            Some(b) if !b.is_macro && b.single != name => {
+                self.map.remove(name);
+                self.names.retain(|n| n != name);
+                Ok(format!("Alias '{}' removed.", name))
+            }
+            Some(b) if !b.is_macro => Err(format!("unalias error: '{}' is a built-in command", name)),
+            _ => Err(format!("unalias error: '{}' is not an alias", name)),
+        }
+    }
+
+    pub fn remove_macro(&mut self, name: &str) -> Result<String, String> {
+        if !self.is_macro_name(name) {
+            return Err(format!("unmacro error: '{}' is not a macro", name));
+        }
+        self.map.remove(name);
+        self.names.retain(|n| n != name);
+        Ok(format!("Macro '{}' removed.", name))
+    }
+
+    /// Aliases and macros as `rename` / `macro` lines, in definition order.
+    pub fn bindings_script(&self) -> String {
+        let mut out = String::new();
+        for name in &self.names {
+            match self.map.get(name) {
+                Some(b) if b.is_macro => out.push_str(&format!("macro {} {}\n", name, b.seq.join(" "))),
+                Some(b) if b.single != *name => out.push_str(&format!("rename {} {}\n", name, b.single)),
+                _ => {}
+            }
+        }
+        out
+    }
+
+    pub fn save_bindings(&self, file: &str) -> Result<String, String> {
+        std::fs::write(file, self.bindings_script()).map_err(|e| format!("Cannot write {}: {}", file, e))?;
+        Ok(format!("Bindings saved to {}.", file))
+    }
+
+    /// Runs the `rename`, `macro`, `unalias` and `unmacro` lines of an rc file
+    /// (`#` starts a comment line); returns one message per line that failed.
+    pub fn load_bindings(&mut self, file: &str) -> Result<Vec<String>, String> {
+        let content = std::fs::read_to_string(file).map_err(|_| format!("Cannot open rc file: {}", file))?;
+        let mut errors = Vec::new();
+        for (n, line) in content.lines().enumerate() {
+            let mut words = line.split_whitespace().map(str::to_string);
+            let res = match (words.next().as_deref(), words.next()) {
+                (None, _) => continue,
+                (Some(w), _) if w.starts_with('#') => continue,
+                (Some("rename"), Some(new_name)) => match words.next() {
+                    Some(old_name) => self.define_alias(&new_name, &old_name),
+                    None => Err("Usage: rename <newname> <existingcommand>".to_string()),
+                },
+                (Some("macro"), Some(name)) => self.define_macro(&name, words.collect()),
+                (Some("unalias"), Some(name)) => self.remove_alias(&name),
+                (Some("unmacro"), Some(name)) => self.remove_macro(&name),
+                _ => Err("expected rename, macro, unalias or unmacro".to_string()),
+            };
+            if let Err(e) = res {
+                errors.push(format!("{}:{}: {}", file, n + 1, e));
+            }
+        }
+        Ok(errors)
     }
 
     pub fn is_macro_name(&self, name: &str) -> bool {
@@ -133,6 +311,174 @@ impl CommandTable {
     pub fn macro_seq(&self, name: &str) -> Option<Vec<String>> {
         self.map.get(name).and_then(|b| if b.is_macro { Some(b.seq.clone()) } else { None })
     }
+
+    /// The shortest prefix of `name` that `resolve_stem` maps back to it.
+    pub fn abbreviation(&self, name: &str) -> Option<String> {
+        name.char_indices()
+            .map(|(i, c)| &name[..i + c.len_utf8()])
+            .find(|p| self.resolve_stem(p).as_deref() == Ok(name))
+            .map(str::to_string)
+    }
+
+    /// `help` with no argument: every command with its shortest abbreviation.
+    pub fn help(&self) -> String {
+        let mut out = format!("  {:<28} {}\n", "command ([n] = multiplier)", "short");
+        for b in BUILTINS {
+            let abbrev = self.abbreviation(b.name).filter(|a| a != b.name).unwrap_or_default();
+            out.push_str(&format!("  {:<28} {:<8} {}\n", b.usage, abbrev, b.description));
+        }
+        let user: Vec<String> = self.names.iter()
+            .filter(|n| BUILTINS.iter().all(|b| b.name != n.as_str()))
+            .map(|n| match self.abbreviation(n).filter(|a| a != n) {
+                Some(a) => format!("{} [{}]", n, a),
+                None => n.clone(),
+            })
+            .collect();
+        if !user.is_empty() {
+            out.push_str(&format!("Your aliases and macros: {}\n", user.join(", ")));
+        }
+        out.push_str("Scripting: set, repeat, if, while (see the README). Type 'help <command>' for details.\n");
+        out
+    }
+
+    /// `help <command>`, accepting any abbreviation.
+    pub fn help_for(&self, stem: &str) -> Result<String, String> {
+        let name = self.resolve_stem(stem)?;
+        let Some(b) = BUILTINS.iter().find(|b| b.name == name) else {
+            return self.describe(&name);
+        };
+        let mut out = format!("{}\n  {}\n", b.usage, b.description);
+        if let Some(a) = self.abbreviation(&name).filter(|a| *a != name) {
+            out.push_str(&format!("  shortest abbreviation: {}\n", a));
+        }
+        Ok(out)
+    }
+
+    /// What `name` stands for: the command behind an alias, or a macro's body
+    /// and full expansion.
+    pub fn describe(&self, name: &str) -> Result<String, String> {
+        let b = self.map.get(name).ok_or_else(|| format!("describe: unknown name '{}'", name))?;
+        if !b.is_macro {
+            if b.single == name {
+                return Ok(format!("'{}' is a built-in command; see 'help {}'.\n", name, name));
+            }
+            return Ok(format!("'{}' is an alias for '{}'.\n", name, b.single));
+        }
+        let mut out = format!("'{}' is a macro: {}\n", name, b.seq.join(" "));
+        if b.arity > 0 {
+            out.push_str(&format!("  takes {} argument(s), $1..${}\n", b.arity, b.arity));
+        } else {
+            match self.expand(name, &[]) {
+                Ok(tokens) if tokens != b.seq => out.push_str(&format!("  expands to: {}\n", tokens.join(" "))),
+                Ok(_) => {}
+                Err(e) => out.push_str(&format!("  {}\n", e)),
+            }
+        }
+        Ok(out)
+    }
+
+    /// Macros that the tokens of `seq` call, as far as they resolve now.
+    fn calls(&self, seq: &[String]) -> Vec<String> {
+        seq.iter()
+            .filter_map(|t| self.parse_command_token(t).ok())
+            .map(|(_, command)| command)
+            .filter(|command| self.is_macro_name(command))
+            .collect()
+    }
+
+    /// A chain of calls leading from macro `name` back to itself.
+    fn find_cycle(&self, name: &str) -> Option<Vec<String>> {
+        let mut path = vec![name.to_string()];
+        let mut seen = HashSet::new();
+        self.cycle_from(name, &mut path, &mut seen).then_some(path)
+    }
+
+    fn cycle_from(&self, start: &str, path: &mut Vec<String>, seen: &mut HashSet<String>) -> bool {
+        let cur = path.last().cloned().unwrap_or_default();
+        let Some(seq) = self.macro_seq(&cur) else { return false };
+        for callee in self.calls(&seq) {
+            if callee == start {
+                path.push(callee);
+                return true;
+            }
+            if seen.insert(callee.clone()) {
+                path.push(callee);
+                if self.cycle_from(start, path, seen) {
+                    return true;
+                }
+                path.pop();
+            }
+        }
+        false
+    }
+
+    /// Number of arguments macro `name` takes (0 for other commands).
+    pub fn macro_arity(&self, name: &str) -> usize {
+        self.map.get(name).map(|b| b.arity).unwrap_or(0)
+    }
+
+    /// Body of macro `name` with `$1`..`$N` replaced by `args`, which must be
+    /// exactly its arity. An argument may carry a multiplier (`3left`).
+    pub fn expand_macro(&self, name: &str, args: &[String]) -> Result<Vec<String>, String> {
+        let seq = self.macro_seq(name).ok_or_else(|| format!("Invalid command: {}", name))?;
+        let arity = self.macro_arity(name);
+        if args.len() != arity {
+            return Err(format!("macro '{}' takes {} argument(s), got {}", name, arity, args.len()));
+        }
+        if arity == 0 {
+            return Ok(seq);
+        }
+        Ok(seq.iter().map(|t| replace_params(t, |n| args[n - 1].clone())).collect())
+    }
+
+    /// Queues `repeat` copies of a macro expansion on `pending`, within the
+    /// expansion limits; past them the whole pending stack is dropped.
+    pub fn push_expansion(&mut self, name: &str, seq: &[String], repeat: i32) -> Result<(), String> {
+        self.expansions.push(&mut self.pending, name, seq, repeat)
+    }
+
+    /// Starts fresh expansion limits; call before reading a token from the input.
+    pub fn reset_expansions(&mut self) {
+        self.expansions = Expansions::default();
+    }
+
+    /// The tokens macro `name` runs with `args`, with nested macros expanded as
+    /// at run time but nothing executed.
+    pub fn expand(&self, name: &str, args: &[String]) -> Result<Vec<String>, String> {
+        let mut limits = Expansions::default();
+        let mut stack = Vec::new();
+        limits.push(&mut stack, name, &self.expand_macro(name, args)?, 1)?;
+        let mut out = Vec::new();
+        while let Some(t) = stack.pop() {
+            match self.parse_command_token(&t) {
+                Ok((repeat, command)) if self.is_macro_name(&command) => {
+                    let args: Vec<String> = (0..self.macro_arity(&command)).map_while(|_| stack.pop()).collect();
+                    let seq = self.expand_macro(&command, &args)?;
+                    limits.push(&mut stack, &command, &seq, repeat)?;
+                }
+                _ => out.push(t),
+            }
+        }
+        Ok(out)
+    }
+}
+
+/// Replaces each `$<digits>` in `token` with `f(number)`.
+fn replace_params(token: &str, mut f: impl FnMut(usize) -> String) -> String {
+    let mut out = String::new();
+    let mut rest = token;
+    while let Some(i) = rest.find('$') {
+        let digits = rest[i + 1..].bytes().take_while(u8::is_ascii_digit).count();
+        out.push_str(&rest[..i]);
+        if digits == 0 {
+            out.push('$');
+        } else {
+            out.push_str(&f(rest[i + 1..i + 1 + digits].parse().unwrap_or(usize::MAX)));
+        }
+        rest = &rest[i + 1 + digits..];
+    }
+    out.push_str(rest);
+    out
 }
 
 // ===== Token sources with newline sentinel =====
@@ -140,16 +486,53 @@ impl CommandTable {
 pub enum Source {
     Stdin,
     File { tokens: VecDeque<String> },
+    /// Read a line at a time, so a FIFO can be fed while the game runs.
+    Reader { reader: BufReader<File>, buf: VecDeque<String> },
 }
 
 pub struct TokenStream {
     pub sources: Vec<Source>,
     stdin_buf: VecDeque<String>,
+    /// Reads stdin lines when it is a terminal; see `use_line_editor`.
+    editor: Option<LineEditor>,
+}
+
+impl Default for TokenStream {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl TokenStream {
     pub fn new() -> Self {
-        Self { sources: vec![Source::Stdin], stdin_buf: VecDeque::new() }
+        Self { sources: vec![Source::Stdin], stdin_buf: VecDeque::new(), editor: None }
+    }
+
+    /// Reads stdin through a line editor with history and tab completion,
+    /// if stdin is a terminal.
+    pub fn use_line_editor(&mut self, history: Option<PathBuf>) {
+        self.editor = LineEditor::new(history);
+    }
+
+    /// Names for tab completion (normally `CommandTable::names`).
+    pub fn set_completions(&mut self, names: &[String]) {
+        if let Some(editor) = &mut self.editor {
+            editor.set_completions(names);
+        }
+    }
+
+    /// A stream reading only `file`, ending where the file does.
+    pub fn from_file(file: &str) -> Result<Self, String> {
+        let mut stream = Self { sources: Vec::new(), stdin_buf: VecDeque::new(), editor: None };
+        stream.push_file(file)?;
+        Ok(stream)
+    }
+
+    /// A stream reading `path` (a file or a FIFO) line by line as tokens are needed.
+    pub fn from_reader(path: &str) -> Result<Self, String> {
+        let file = File::open(path).map_err(|e| format!("Cannot open {}: {}", path, e))?;
+        let source = Source::Reader { reader: BufReader::new(file), buf: VecDeque::new() };
+        Ok(Self { sources: vec![source], stdin_buf: VecDeque::new(), editor: None })
     }
 
     pub fn push_file(&mut self, file: &str) -> Result<(), String> {
@@ -183,14 +566,32 @@ impl TokenStream {
                         continue;
                     }
                 }
+                Source::Reader { reader, buf } => {
+                    if let Some(t) = buf.pop_front() {
+                        return Some(t);
+                    }
+                    let mut line = String::new();
+                    if reader.read_line(&mut line).unwrap_or(0) == 0 {
+                        self.sources.pop();
+                        continue;
+                    }
+                    buf.extend(line.split_whitespace().map(str::to_string));
+                    buf.push_back("\n".to_string());
+                }
                 Source::Stdin => {
                     if let Some(t) = self.stdin_buf.pop_front() {
                         return Some(t);
                     }
                     // read a new line
-                    let mut line = String::new();
-                    let n = std::io::stdin().read_line(&mut line).ok()?;
-                    if n == 0 { return None; } // EOF
+                    let line = match &mut self.editor {
+                        Some(editor) => editor.read_line()?,
+                        None => {
+                            let mut line = String::new();
+                            let n = std::io::stdin().read_line(&mut line).ok()?;
+                            if n == 0 { return None; } // EOF
+                            line
+                        }
+                    };
                     for tok in line.split_whitespace() {
                         self.stdin_buf.push_back(tok.to_string());
                     }
@@ -1,3 +1,9 @@
+use std::time::Duration;
+
+use crate::controller::{Controller, ControllerSpec};
+use crate::game::Game;
+use crate::level::LevelSet;
+
 #[derive(Debug, Clone)]
 pub struct Config {
     pub text_only: bool,
@@ -5,6 +11,37 @@ pub struct Config {
     pub level: i32,
     pub script_file1: String,
     pub script_file2: String,
+    pub stats_json: Option<String>,
+    pub stats_csv: Option<String>,
+    /// Controller specs (see `controller::ControllerSpec`) for players the
+    /// program plays itself; `None` means a human at the keyboard.
+    pub controller1: Option<String>,
+    pub controller2: Option<String>,
+    pub bot_timeout_ms: u64,
+    /// Host a networked game on this address (see `net`).
+    pub serve: Option<String>,
+    /// Join a networked game at this address.
+    pub connect: Option<String>,
+    pub spectate: bool,
+    /// How far spectators' view lags behind the game; applied by the host (`-serve`).
+    pub spectate_delay: Duration,
+    pub name: Option<String>,
+    /// Aliases and macros loaded by the text interpreter at startup; see `rc_path`.
+    pub rcfile: Option<String>,
+    /// Text mode without rendering: one JSON line per command (see `batch`).
+    pub batch: bool,
+    /// Batch commands come from this file instead of stdin.
+    pub batch_file: Option<String>,
+    /// In batch mode, exit non-zero if any command was invalid.
+    pub strict: bool,
+    /// Text mode: read each player's commands from their own file or FIFO
+    /// instead of the shared input.
+    pub input1: Option<String>,
+    pub input2: Option<String>,
+    /// JSON level definitions replacing the built-in levels 0-4 (see `level::LevelSet`).
+    pub level_file: Option<String>,
+    /// Marathon mode: each player goes up a level after this many cleared lines.
+    pub lines_per_level: Option<i32>,
 }
 
 impl Default for Config {
@@ -15,11 +52,71 @@ impl Default for Config {
             level: 0,
             script_file1: "tetris_sequence1.txt".to_string(),
             script_file2: "tetris_sequence2.txt".to_string(),
+            stats_json: None,
+            stats_csv: None,
+            controller1: None,
+            controller2: None,
+            bot_timeout_ms: crate::protocol::DEFAULT_TIMEOUT_MS,
+            serve: None,
+            connect: None,
+            spectate: false,
+            spectate_delay: Duration::ZERO,
+            name: None,
+            rcfile: None,
+            batch: false,
+            batch_file: None,
+            strict: false,
+            input1: None,
+            input2: None,
+            level_file: None,
+            lines_per_level: None,
+        }
+    }
+}
+
+impl Config {
+    /// Builds the controllers for both players (`None` for human players).
+    pub fn build_controllers(&self) -> Result<[Option<Box<dyn Controller>>; 2], String> {
+        let timeout = Duration::from_millis(self.bot_timeout_ms);
+        let seed = self.seed.unwrap_or(12345);
+        let build = |spec: &Option<String>, offset: u64| -> Result<Option<Box<dyn Controller>>, String> {
+            match spec {
+                None => Ok(None),
+                Some(s) => ControllerSpec::parse(s)?.build(seed.wrapping_add(offset), timeout).map(Some),
+            }
+        };
+        Ok([build(&self.controller1, 0)?, build(&self.controller2, 1)?])
+    }
+
+    /// The levels from `-levelfile`, else the built-in ones.
+    pub fn level_set(&self) -> Result<LevelSet, String> {
+        match &self.level_file {
+            Some(path) => LevelSet::load(path),
+            None => Ok(LevelSet::builtin()),
         }
     }
+
+    /// A new game with these settings.
+    pub fn new_game(&self) -> Result<Game, String> {
+        let mut game = Game::with_levels(self.seed, self.level, self.script_file1.clone(), self.script_file2.clone(), self.level_set()?)?;
+        game.lines_per_level = self.lines_per_level;
+        Ok(game)
+    }
+
+    /// The `-rcfile` given, else `~/.tetrisrc` when it exists.
+    pub fn rc_path(&self) -> Option<String> {
+        if let Some(f) = &self.rcfile {
+            return Some(f.clone());
+        }
+        let home = std::env::var_os("HOME")?;
+        let path = std::path::Path::new(&home).join(".tetrisrc");
+        path.exists().then(|| path.to_string_lossy().into_owned())
+    }
 }
 
-pub fn parse_args(args: &[String]) -> Config {
+/// Parses the shared flags; unknown flags are left for the binaries. Fails on
+/// values that would be unsafe to use, such as a `-delay` no `Duration` can hold.
+pub fn parse_args(args: &[String]) -> Result<Config, String> {
     let mut cfg = Config::default();
     let mut i = 0;
     while i < args.len() {
@@ -28,37 +125,108 @@ pub fn parse_args(args: &[String]) -> Config {
             "-text" => {
                 cfg.text_only = true;
             }
-            "-seed" => {
-                if i + 1 < args.len() {
-                    if let Ok(v) = args[i + 1].parse::<u64>() {
-                        cfg.seed = Some(v);
-                    }
-                    i += 1;
+            "-seed" if i + 1 < args.len() => {
+                if let Ok(v) = args[i + 1].parse::<u64>() {
+                    cfg.seed = Some(v);
                 }
+                i += 1;
             }
-            "-scriptfile1" => {
-                if i + 1 < args.len() {
-                    cfg.script_file1 = args[i + 1].clone();
-                    i += 1;
+            "-scriptfile1" if i + 1 < args.len() => {
+                cfg.script_file1 = args[i + 1].clone();
+                i += 1;
+            }
+            "-scriptfile2" if i + 1 < args.len() => {
+                cfg.script_file2 = args[i + 1].clone();
+                i += 1;
+            }
+            "-statsjson" if i + 1 < args.len() => {
+                cfg.stats_json = Some(args[i + 1].clone());
+                i += 1;
+            }
+            "-statscsv" if i + 1 < args.len() => {
+                cfg.stats_csv = Some(args[i + 1].clone());
+                i += 1;
+            }
+            "-bot2" if i + 1 < args.len() => {
+                cfg.controller2 = Some(format!("bot:{}", args[i + 1]));
+                i += 1;
+            }
+            "-p1" if i + 1 < args.len() => {
+                cfg.controller1 = Some(args[i + 1].clone());
+                i += 1;
+            }
+            "-p2" if i + 1 < args.len() => {
+                cfg.controller2 = Some(args[i + 1].clone());
+                i += 1;
+            }
+            "-bottimeout" if i + 1 < args.len() => {
+                if let Ok(v) = args[i + 1].parse::<u64>() {
+                    cfg.bot_timeout_ms = v;
                 }
+                i += 1;
+            }
+            "-serve" if i + 1 < args.len() => {
+                cfg.serve = Some(crate::net::with_default_port(&args[i + 1]));
+                i += 1;
+            }
+            "-connect" if i + 1 < args.len() => {
+                cfg.connect = Some(crate::net::with_default_port(&args[i + 1]));
+                i += 1;
+            }
+            "-spectate" => {
+                cfg.spectate = true;
+            }
+            "-delay" if i + 1 < args.len() => {
+                cfg.spectate_delay = args[i + 1].parse::<f64>().ok()
+                    .and_then(|v| Duration::try_from_secs_f64(v).ok())
+                    .ok_or_else(|| format!("-delay: bad number of seconds '{}'", args[i + 1]))?;
+                i += 1;
             }
-            "-scriptfile2" => {
-                if i + 1 < args.len() {
-                    cfg.script_file2 = args[i + 1].clone();
+            "-name" if i + 1 < args.len() => {
+                cfg.name = Some(args[i + 1].clone());
+                i += 1;
+            }
+            "-rcfile" if i + 1 < args.len() => {
+                cfg.rcfile = Some(args[i + 1].clone());
+                i += 1;
+            }
+            "-batch" => {
+                cfg.batch = true;
+                if i + 1 < args.len() && !args[i + 1].starts_with('-') {
+                    cfg.batch_file = Some(args[i + 1].clone());
                     i += 1;
                 }
             }
-            "-startlevel" => {
-                if i + 1 < args.len() {
-                    if let Ok(v) = args[i + 1].parse::<i32>() {
-                        cfg.level = v;
-                    }
-                    i += 1;
+            "-input1" if i + 1 < args.len() => {
+                cfg.input1 = Some(args[i + 1].clone());
+                i += 1;
+            }
+            "-input2" if i + 1 < args.len() => {
+                cfg.input2 = Some(args[i + 1].clone());
+                i += 1;
+            }
+            "-strict" => {
+                cfg.strict = true;
+            }
+            "-levelfile" if i + 1 < args.len() => {
+                cfg.level_file = Some(args[i + 1].clone());
+                i += 1;
+            }
+            "-marathon" if i + 1 < args.len() => {
+                if let Ok(v) = args[i + 1].parse::<i32>() {
+                    cfg.lines_per_level = (v > 0).then_some(v);
+                }
+                i += 1;
+            }
+            "-startlevel" if i + 1 < args.len() => {
+                if let Ok(v) = args[i + 1].parse::<i32>() {
+                    cfg.level = v;
                 }
+                i += 1;
             }
             _ => {}
         }
         i += 1;
     }
-    cfg
+    Ok(cfg)
 }
@@ -0,0 +1,266 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::block::BlockKind;
+use crate::bot::{BotController, Difficulty};
+use crate::commands::CommandTable;
+use crate::game::Game;
+use crate::player::Move;
+use crate::protocol::ExternalBot;
+
+/// A special action chosen after clearing 2+ rows on a drop.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpecialAction {
+    Blind,
+    Heavy,
+    Force(BlockKind),
+}
+
+impl SpecialAction {
+    pub fn name(self) -> &'static str {
+        match self {
+            SpecialAction::Blind => "blind",
+            SpecialAction::Heavy => "heavy",
+            SpecialAction::Force(_) => "force",
+        }
+    }
+
+    pub fn param(self) -> Option<String> {
+        match self {
+            SpecialAction::Force(k) => Some(k.to_char().to_string()),
+            _ => None,
+        }
+    }
+
+    pub fn parse(action: &str, param: Option<&str>) -> Option<Self> {
+        match action {
+            "blind" => Some(SpecialAction::Blind),
+            "heavy" => Some(SpecialAction::Heavy),
+            "force" => {
+                let mut it = param?.chars();
+                let kind = BlockKind::from_char(it.next()?)?;
+                if it.next().is_some() || kind == BlockKind::Star { return None; }
+                Some(SpecialAction::Force(kind))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Something that plays one side of a `Game` without a frontend.
+///
+/// The driver asks for one move at a time until the current piece lands, so a
+/// controller may either plan a whole placement up front or react move by move.
+pub trait Controller: Send {
+    fn next_move(&mut self, game: &Game, player: i32) -> Move;
+    fn choose_special(&mut self, game: &Game, player: i32) -> SpecialAction;
+}
+
+/// Uniformly random inputs; useful as a baseline and for fuzzing the rules.
+pub struct RandomController {
+    rng: StdRng,
+}
+
+impl RandomController {
+    pub fn new(seed: u64) -> Self {
+        Self { rng: StdRng::seed_from_u64(seed) }
+    }
+}
+
+impl Controller for RandomController {
+    fn next_move(&mut self, _game: &Game, _player: i32) -> Move {
+        Move::ALL[self.rng.gen_range(0..Move::ALL.len())]
+    }
+
+    fn choose_special(&mut self, _game: &Game, _player: i32) -> SpecialAction {
+        match self.rng.gen_range(0..3) {
+            0 => SpecialAction::Blind,
+            1 => SpecialAction::Heavy,
+            _ => SpecialAction::Force(BlockKind::Z),
+        }
+    }
+}
+
+/// Replays a command script (same tokens as the text interpreter: multipliers,
+/// `rename` and `macro` lines work), looping when it runs out.
+/// Non-movement commands other than special-action answers are skipped.
+pub struct ScriptController {
+    table: CommandTable,
+    script: Vec<String>,
+    pos: usize,
+    queued: VecDeque<Move>,
+}
+
+impl ScriptController {
+    pub fn from_file(file: &str) -> Result<Self, String> {
+        let content = std::fs::read_to_string(file)
+            .map_err(|_| format!("Cannot open script file: {}", file))?;
+        Ok(Self::from_text(&content))
+    }
+
+    pub fn from_text(text: &str) -> Self {
+        let mut script = Vec::new();
+        for line in text.lines() {
+            for tok in line.split_whitespace() {
+                script.push(tok.to_string());
+            }
+            script.push("\n".to_string());
+        }
+        Self { table: CommandTable::new(), script, pos: 0, queued: VecDeque::new() }
+    }
+
+    fn next_token(&mut self) -> Option<String> {
+        if let Some(t) = self.table.pending.pop() {
+            return Some(t);
+        }
+        self.table.reset_expansions();
+        if self.script.is_empty() { return None; }
+        let t = self.script[self.pos].clone();
+        self.pos = (self.pos + 1) % self.script.len();
+        Some(t)
+    }
+
+    fn next_word(&mut self) -> Option<String> {
+        for _ in 0..=self.script.len() {
+            match self.next_token() {
+                Some(t) if t == "\n" => continue,
+                other => return other,
+            }
+        }
+        None
+    }
+
+    fn rest_of_line(&mut self) -> Vec<String> {
+        let mut out = Vec::new();
+        // bounded so a script without newlines cannot spin forever
+        for _ in 0..self.script.len() {
+            match self.next_token() {
+                Some(t) if t != "\n" => out.push(t),
+                _ => break,
+            }
+        }
+        out
+    }
+}
+
+impl Controller for ScriptController {
+    fn next_move(&mut self, _game: &Game, _player: i32) -> Move {
+        if let Some(mv) = self.queued.pop_front() {
+            return mv;
+        }
+        // one full pass over the script (plus whatever macros expand to) without a move means there is none
+        let mut budget = self.script.len() * 4 + 64;
+        while budget > 0 {
+            budget -= 1;
+            let Some(token) = self.next_token() else { break };
+            if token == "\n" { continue; }
+            let Ok((repeat, command)) = self.table.parse_command_token(&token) else { continue };
+
+            if self.table.is_macro_name(&command) {
+                let arity = self.table.macro_arity(&command);
+                let mut args = Vec::new();
+                while args.len() < arity {
+                    match self.next_token() {
+                        Some(t) if t != "\n" => args.push(t),
+                        _ => break,
+                    }
+                }
+                if let Ok(seq) = self.table.expand_macro(&command, &args) {
+                    let _ = self.table.push_expansion(&command, &seq, repeat);
+                }
+                continue;
+            }
+            if let Some(mv) = Move::from_command(&command) {
+                for _ in 0..repeat {
+                    self.queued.push_back(mv);
+                }
+                return self.queued.pop_front().unwrap_or(Move::Drop);
+            }
+            match command.as_str() {
+                "rename" => {
+                    if let (Some(new_name), Some(old_name)) = (self.next_word(), self.next_word()) {
+                        let _ = self.table.define_alias(&new_name, &old_name);
+                    }
+                }
+                "macro" => {
+                    if let Some(name) = self.next_word() {
+                        let body = self.rest_of_line();
+                        let _ = self.table.define_macro(&name, body);
+                    }
+                }
+                "unalias" => {
+                    if let Some(name) = self.next_word() {
+                        let _ = self.table.remove_alias(&name);
+                    }
+                }
+                "unmacro" => {
+                    if let Some(name) = self.next_word() {
+                        let _ = self.table.remove_macro(&name);
+                    }
+                }
+                "sequence" | "norandom" | "save-bindings" => {
+                    // filename argument; file-level commands are not replayed here
+                    let _ = self.next_word();
+                }
+                _ => {}
+            }
+        }
+        Move::Drop
+    }
+
+    fn choose_special(&mut self, _game: &Game, _player: i32) -> SpecialAction {
+        let parsed = match self.next_word().as_deref() {
+            Some("force") => {
+                let block = self.next_word();
+                SpecialAction::parse("force", block.as_deref())
+            }
+            Some(a) => SpecialAction::parse(a, None),
+            None => None,
+        };
+        parsed.unwrap_or(SpecialAction::Heavy)
+    }
+}
+
+/// A controller named on the command line:
+/// `random`, `script:<file>`, `bot[:easy|medium|hard]` (default medium),
+/// or `exec:<command>` for an external bot speaking the `protocol`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ControllerSpec {
+    Random,
+    Script(String),
+    Bot(Difficulty),
+    Exec(String),
+}
+
+impl ControllerSpec {
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let (kind, arg) = match spec.split_once(':') {
+            Some((k, a)) => (k, Some(a)),
+            None => (spec, None),
+        };
+        match (kind, arg) {
+            ("random", _) => Ok(ControllerSpec::Random),
+            ("script", Some(file)) => Ok(ControllerSpec::Script(file.to_string())),
+            ("script", None) => Err("script controller needs a file: script:<file>".to_string()),
+            ("bot", level) => Difficulty::from_name(level.unwrap_or("medium"))
+                .map(ControllerSpec::Bot)
+                .ok_or_else(|| format!("Unknown bot difficulty '{}'", level.unwrap_or(""))),
+            ("exec", Some(cmd)) if !cmd.trim().is_empty() => Ok(ControllerSpec::Exec(cmd.to_string())),
+            ("exec", _) => Err("exec controller needs a command: exec:<command>".to_string()),
+            _ => Err(format!("Unknown controller '{}'", spec)),
+        }
+    }
+
+    /// `seed` feeds the random and bot controllers; `timeout` bounds external bot replies.
+    pub fn build(&self, seed: u64, timeout: Duration) -> Result<Box<dyn Controller>, String> {
+        match self {
+            ControllerSpec::Random => Ok(Box::new(RandomController::new(seed))),
+            ControllerSpec::Script(file) => Ok(Box::new(ScriptController::from_file(file)?)),
+            ControllerSpec::Bot(d) => Ok(Box::new(BotController::new(*d, seed))),
+            ControllerSpec::Exec(cmd) => Ok(Box::new(ExternalBot::spawn(cmd, timeout)?)),
+        }
+    }
+}
@@ -12,6 +12,13 @@ impl Effect {
         Effect::Heavy { expired: false }
     }
 
+    pub fn name(&self) -> &'static str {
+        match self {
+            Effect::Blind { .. } => "blind",
+            Effect::Heavy { .. } => "heavy",
+        }
+    }
+
     pub fn is_blind(&self) -> bool {
         matches!(self, Effect::Blind { .. })
     }
@@ -0,0 +1,427 @@
+//! Gym-style environment for training agents on this ruleset.
+//!
+//! The agent plays player 1, one input per `step`; whenever its block lands
+//! the opponent (any `Controller`) plays a whole turn before control returns.
+//! After a clear of 2+ rows the next step must be a special action.
+//!
+//! ```no_run
+//! use tetris::env::{Env, EnvConfig, ShapedReward};
+//!
+//! let mut env = Env::new(EnvConfig::default()).with_reward(ShapedReward::default());
+//! let mut obs = env.reset(7).unwrap();
+//! loop {
+//!     let action = env.legal_actions()[0]; // your policy here, fed with obs.to_vec()
+//!     let (next, reward, done, _info) = env.step(action).unwrap();
+//!     println!("reward {}", reward);
+//!     obs = next;
+//!     if done { break; }
+//! }
+//! # let _ = obs;
+//! ```
+
+use std::time::Duration;
+
+use crate::block::BlockKind;
+use crate::controller::{Controller, ControllerSpec, SpecialAction};
+use crate::bot::Difficulty;
+use crate::game::Game;
+use crate::grid::{Grid, COLS, PLAY_BOTTOM, PLAY_TOP};
+use crate::player::{Move, MoveOutcome, PlayerState};
+use crate::render_text::in_blind;
+use crate::sim::{self, MAX_INPUTS_PER_PIECE};
+
+pub const ROWS: usize = PLAY_BOTTOM - PLAY_TOP + 1;
+
+/// Cell codes in observations: 0 empty, 1..=8 a block kind (see `kind_code`), 9 hidden by blind.
+pub const HIDDEN: u8 = 9;
+
+/// Blocks that can be forced, in action-index order.
+const FORCE_KINDS: [BlockKind; 7] = [BlockKind::I, BlockKind::J, BlockKind::L, BlockKind::O, BlockKind::S, BlockKind::T, BlockKind::Z];
+
+/// 6 moves, blind, heavy, and 7 force targets.
+pub const ACTION_COUNT: usize = 15;
+
+/// Length of `Observation::to_vec`.
+pub const OBS_LEN: usize = 3 * ROWS * COLS + 9;
+
+/// 1..=8 for T, S, Z, I, J, L, O and star.
+pub fn kind_code(kind: BlockKind) -> u8 {
+    kind as u8 + 1
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Move(Move),
+    Special(SpecialAction),
+}
+
+impl Action {
+    /// Discrete action space: 0..6 `Move::ALL`, 6 blind, 7 heavy, 8..15 force I/J/L/O/S/T/Z.
+    pub fn from_index(i: usize) -> Option<Self> {
+        match i {
+            0..=5 => Some(Action::Move(Move::ALL[i])),
+            6 => Some(Action::Special(SpecialAction::Blind)),
+            7 => Some(Action::Special(SpecialAction::Heavy)),
+            8..=14 => Some(Action::Special(SpecialAction::Force(FORCE_KINDS[i - 8]))),
+            _ => None,
+        }
+    }
+
+    pub fn index(self) -> usize {
+        match self {
+            Action::Move(mv) => Move::ALL.iter().position(|&m| m == mv).unwrap_or(0),
+            Action::Special(SpecialAction::Blind) => 6,
+            Action::Special(SpecialAction::Heavy) => 7,
+            Action::Special(SpecialAction::Force(k)) => 8 + FORCE_KINDS.iter().position(|&f| f == k).unwrap_or(0),
+        }
+    }
+}
+
+/// What the agent sees. All arrays have fixed sizes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Observation {
+    /// Settled cells of the agent's play area, top row first.
+    pub board: [[u8; COLS]; ROWS],
+    /// 1 where the agent's falling block is.
+    pub falling: [[u8; COLS]; ROWS],
+    pub opponent: [[u8; COLS]; ROWS],
+    pub current: u8,
+    pub next: u8,
+    pub level: u8,
+    pub heavy_level: bool,
+    pub blind: bool,
+    /// Active heavy effects on the agent.
+    pub heavy_effects: u8,
+    pub opponent_blind: bool,
+    pub opponent_heavy_effects: u8,
+    /// The next action must be a special action.
+    pub awaiting_special: bool,
+}
+
+impl Observation {
+    fn from_game(game: &Game, awaiting_special: bool) -> Self {
+        let (me, them) = (&game.p1, &game.p2);
+        let mut falling = [[0; COLS]; ROWS];
+        for c in &me.cur.cells {
+            if let Some(row) = (c.r as usize).checked_sub(PLAY_TOP).filter(|&r| r < ROWS) {
+                falling[row][c.c as usize] = 1;
+            }
+        }
+        Self {
+            board: settled_cells(me),
+            falling,
+            opponent: settled_cells(them),
+            current: kind_code(me.cur.kind),
+            next: kind_code(me.next_kind),
+            level: me.level.number() as u8,
+            heavy_level: me.level.is_heavy(),
+            blind: me.has_blind(),
+            heavy_effects: heavy_effects(me),
+            opponent_blind: them.has_blind(),
+            opponent_heavy_effects: heavy_effects(them),
+            awaiting_special,
+        }
+    }
+
+    /// Flat vector of length `OBS_LEN`: the three planes row by row, then the scalars.
+    pub fn to_vec(&self) -> Vec<f32> {
+        let mut v = Vec::with_capacity(OBS_LEN);
+        for plane in [&self.board, &self.falling, &self.opponent] {
+            v.extend(plane.iter().flatten().map(|&x| x as f32));
+        }
+        v.extend([
+            self.current as f32,
+            self.next as f32,
+            self.level as f32,
+            self.heavy_level as u8 as f32,
+            self.blind as u8 as f32,
+            self.heavy_effects as f32,
+            self.opponent_blind as u8 as f32,
+            self.opponent_heavy_effects as f32,
+            self.awaiting_special as u8 as f32,
+        ]);
+        v
+    }
+}
+
+fn heavy_effects(p: &PlayerState) -> u8 {
+    p.effects.iter().filter(|e| e.adds_heavy_on_horizontal()).count() as u8
+}
+
+/// The play area without the falling block, masked the way the player sees it under blind.
+fn settled_cells(p: &PlayerState) -> [[u8; COLS]; ROWS] {
+    let blind = p.has_blind();
+    let own: Vec<(i32, i32)> = p.cur.cells.iter().map(|c| (c.r, c.c)).collect();
+    let m = p.grid.matrix();
+    let mut out = [[0; COLS]; ROWS];
+    for r in PLAY_TOP..=PLAY_BOTTOM {
+        for c in 0..COLS {
+            out[r - PLAY_TOP][c] = if blind && in_blind(r, c) {
+                HIDDEN
+            } else if own.contains(&(r as i32, c as i32)) {
+                0
+            } else {
+                BlockKind::from_char(m[r][c]).map(kind_code).unwrap_or(0)
+            };
+        }
+    }
+    out
+}
+
+fn settled_grid(p: &PlayerState) -> Grid {
+    let mut g = p.grid.clone();
+    p.cur.clear_from_grid(&mut g);
+    g
+}
+
+/// What happened during one step, for reward functions and logging.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StepInfo {
+    /// The agent's block locked this step.
+    pub landed: bool,
+    pub lines_cleared: i32,
+    pub score_delta: i32,
+    pub score: i32,
+    pub holes: i32,
+    pub holes_delta: i32,
+    pub stack_height: i32,
+    pub height_delta: i32,
+    /// The agent earned a special action and must choose it next step.
+    pub special_earned: bool,
+    /// The action did not fit the state (a special when none is due, or a move while one is) and was ignored.
+    pub invalid_action: bool,
+    pub opponent_lines: i32,
+    /// `Some(true)` if the opponent topped out, `Some(false)` if the agent did.
+    pub won: Option<bool>,
+    /// The episode hit `EnvConfig::max_pieces`.
+    pub truncated: bool,
+}
+
+/// Reward shaping: turns what happened in a step into a scalar.
+pub trait Reward: Send {
+    fn reward(&mut self, info: &StepInfo) -> f64;
+}
+
+impl<F: FnMut(&StepInfo) -> f64 + Send> Reward for F {
+    fn reward(&mut self, info: &StepInfo) -> f64 {
+        self(info)
+    }
+}
+
+/// The game's own score gained this step.
+#[derive(Debug, Clone, Default)]
+pub struct ScoreReward;
+
+impl Reward for ScoreReward {
+    fn reward(&mut self, info: &StepInfo) -> f64 {
+        info.score_delta as f64
+    }
+}
+
+/// Weighted sum of common shaping terms, evaluated when a block lands
+/// (plus the terminal win/loss bonus).
+#[derive(Debug, Clone)]
+pub struct ShapedReward {
+    /// Reward for clearing 1, 2, 3 and 4+ rows at once.
+    pub lines: [f64; 4],
+    pub per_piece: f64,
+    pub per_hole: f64,
+    pub per_height: f64,
+    pub win: f64,
+    pub loss: f64,
+    pub invalid_action: f64,
+}
+
+impl Default for ShapedReward {
+    fn default() -> Self {
+        Self {
+            lines: [1.0, 3.0, 5.0, 8.0],
+            per_piece: 0.01,
+            per_hole: -0.3,
+            per_height: -0.05,
+            win: 10.0,
+            loss: -10.0,
+            invalid_action: -0.1,
+        }
+    }
+}
+
+impl Reward for ShapedReward {
+    fn reward(&mut self, info: &StepInfo) -> f64 {
+        let mut r = 0.0;
+        if info.invalid_action {
+            r += self.invalid_action;
+        }
+        if info.landed {
+            r += self.per_piece;
+            if info.lines_cleared > 0 {
+                r += self.lines[(info.lines_cleared.min(4) - 1) as usize];
+            }
+            r += self.per_hole * info.holes_delta as f64;
+            r += self.per_height * info.height_delta as f64;
+        }
+        match info.won {
+            Some(true) => r += self.win,
+            Some(false) => r += self.loss,
+            None => {}
+        }
+        r
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct EnvConfig {
+    pub level: i32,
+    pub script1: String,
+    pub script2: String,
+    /// Plays player 2; the default medium bot survives a long time at low levels.
+    pub opponent: ControllerSpec,
+    /// Agent pieces before the episode is truncated.
+    pub max_pieces: u32,
+    pub bot_timeout: Duration,
+}
+
+impl Default for EnvConfig {
+    fn default() -> Self {
+        Self {
+            level: 0,
+            script1: "tetris_sequence1.txt".to_string(),
+            script2: "tetris_sequence2.txt".to_string(),
+            opponent: ControllerSpec::Bot(Difficulty::Medium),
+            max_pieces: 500,
+            bot_timeout: Duration::from_millis(crate::protocol::DEFAULT_TIMEOUT_MS),
+        }
+    }
+}
+
+pub struct Env {
+    pub config: EnvConfig,
+    reward: Box<dyn Reward>,
+    game: Option<Game>,
+    opponent: Option<Box<dyn Controller>>,
+    awaiting_special: bool,
+    inputs_this_piece: u32,
+    pieces: u32,
+    done: bool,
+}
+
+impl Env {
+    /// An environment rewarding the game score; see `with_reward`.
+    pub fn new(config: EnvConfig) -> Self {
+        Self {
+            config,
+            reward: Box::new(ScoreReward),
+            game: None,
+            opponent: None,
+            awaiting_special: false,
+            inputs_this_piece: 0,
+            pieces: 0,
+            done: true,
+        }
+    }
+
+    pub fn with_reward(mut self, reward: impl Reward + 'static) -> Self {
+        self.reward = Box::new(reward);
+        self
+    }
+
+    /// The underlying game, e.g. for rendering (`None` before the first `reset`).
+    pub fn game(&self) -> Option<&Game> {
+        self.game.as_ref()
+    }
+
+    /// Starts a new episode. The same seed replays the same pieces and opponent.
+    pub fn reset(&mut self, seed: u64) -> Result<Observation, String> {
+        let mut game = Game::new(Some(seed), self.config.level, self.config.script1.clone(), self.config.script2.clone())?;
+        game.quiet = true;
+        game.track_finesse = false;
+        self.opponent = Some(self.config.opponent.build(seed.wrapping_add(1), self.config.bot_timeout)?);
+        let obs = Observation::from_game(&game, false);
+        self.game = Some(game);
+        self.awaiting_special = false;
+        self.inputs_this_piece = 0;
+        self.pieces = 0;
+        self.done = false;
+        Ok(obs)
+    }
+
+    /// Plays one agent action. Stepping a finished episode returns its final
+    /// observation with zero reward; stepping before the first `reset` is an error.
+    pub fn step(&mut self, action: Action) -> Result<(Observation, f64, bool, StepInfo), String> {
+        let mut info = StepInfo::default();
+        let Some(game) = self.game.as_mut() else {
+            return Err("step() called before reset()".to_string());
+        };
+        if self.done {
+            return Ok((Observation::from_game(game, self.awaiting_special), 0.0, true, info));
+        }
+        let score_before = game.p1.grid.cur_score();
+        let settled = settled_grid(&game.p1);
+        let (holes_before, height_before) = (settled.count_holes(), settled.stack_height());
+        let opp_lines_before = game.p2.stats.lines_cleared;
+
+        let mut opponent_turn = false;
+        match (action, self.awaiting_special) {
+            (Action::Special(sa), true) => {
+                let param = sa.param();
+                game.apply_special_action(1, sa.name(), param.as_deref());
+                self.awaiting_special = false;
+                if game.running {
+                    game.end_turn(1);
+                    opponent_turn = true;
+                }
+            }
+            (Action::Move(mv), false) => {
+                self.inputs_this_piece += 1;
+                // same stall guard as the sim: a wandering agent gets its block dropped
+                let mv = if self.inputs_this_piece > MAX_INPUTS_PER_PIECE { Move::Drop } else { mv };
+                if game.apply_move(1, mv) == MoveOutcome::Landed {
+                    info.landed = true;
+                    info.lines_cleared = game.p1.last_cleared;
+                    self.inputs_this_piece = 0;
+                    self.pieces += 1;
+                    if game.running {
+                        if game.finish_drop(1) {
+                            self.awaiting_special = true;
+                            info.special_earned = true;
+                        } else {
+                            game.end_turn(1);
+                            opponent_turn = true;
+                        }
+                    }
+                }
+            }
+            _ => info.invalid_action = true,
+        }
+
+        if opponent_turn && game.running {
+            if let Some(opp) = self.opponent.as_mut() {
+                sim::play_turn(game, 2, opp.as_mut());
+            }
+        }
+
+        let settled = settled_grid(&game.p1);
+        info.score = game.p1.grid.cur_score();
+        info.score_delta = info.score - score_before;
+        info.holes = settled.count_holes();
+        info.holes_delta = info.holes - holes_before;
+        info.stack_height = settled.stack_height();
+        info.height_delta = info.stack_height - height_before;
+        info.opponent_lines = (game.p2.stats.lines_cleared - opp_lines_before) as i32;
+        if !game.running {
+            info.won = game.loser.map(|l| l == 2);
+        } else if self.pieces >= self.config.max_pieces {
+            info.truncated = true;
+        }
+        self.done = !game.running || info.truncated;
+
+        let reward = self.reward.reward(&info);
+        Ok((Observation::from_game(game, self.awaiting_special), reward, self.done, info))
+    }
+
+    /// Actions that fit the current state (moves, or the special actions when one is due).
+    pub fn legal_actions(&self) -> Vec<Action> {
+        let range = if self.awaiting_special { 6..ACTION_COUNT } else { 0..6 };
+        range.filter_map(Action::from_index).collect()
+    }
+}
@@ -0,0 +1,38 @@
+use serde::{Deserialize, Serialize};
+
+/// Something worth announcing that happened during play (for spectators and logs).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum GameEvent {
+    LinesCleared { player: i32, lines: i32 },
+    SpecialAction { player: i32, action: String, block: Option<char> },
+    GameOver { loser: i32 },
+    /// Marathon progression moved `player` up to `level`.
+    LevelChanged { player: i32, level: i32 },
+}
+
+impl GameEvent {
+    pub fn describe(&self) -> String {
+        match self {
+            GameEvent::LinesCleared { player, lines } => {
+                let what = match lines {
+                    1 => "a single".to_string(),
+                    2 => "a double".to_string(),
+                    3 => "a triple".to_string(),
+                    4 => "a tetris".to_string(),
+                    n => format!("{} lines", n),
+                };
+                format!("Player {} cleared {}.", player, what)
+            }
+            GameEvent::SpecialAction { player, action, block } => {
+                let victim = if *player == 1 { 2 } else { 1 };
+                match block {
+                    Some(b) => format!("Player {} forced a {} on player {}.", player, b, victim),
+                    None => format!("Player {} sent {} to player {}.", player, action, victim),
+                }
+            }
+            GameEvent::GameOver { loser } => format!("Game over, player {} lost.", loser),
+            GameEvent::LevelChanged { player, level } => format!("Player {} reached level {}.", player, level),
+        }
+    }
+}
@@ -0,0 +1,387 @@
+//! C ABI for embedding the engine (built into the `cdylib`).
+//!
+//! The header is `include/tetris.h`, generated with
+//! `cbindgen --config cbindgen.toml --output include/tetris.h`.
+//!
+//! ```c
+//! TetrisGame *g = tetris_new("-seed 3 -startlevel 2");
+//! tetris_command(g, "3left cw drop");
+//! if (tetris_awaiting_special(g)) tetris_command(g, "force Z");
+//! char *json = tetris_state_json(g);
+//! tetris_string_free(json);
+//! tetris_free(g);
+//! ```
+//!
+//! Functions that can fail return NULL or -1; `tetris_last_error` then holds
+//! the message. A panic inside the engine is caught at the boundary and
+//! reported the same way. Players are numbered 1 and 2.
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::ffi::{c_char, c_int, CStr, CString};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+
+use crate::block::BlockKind;
+use crate::commands::CommandTable;
+use crate::config::parse_args;
+use crate::controller::SpecialAction;
+use crate::game::Game;
+use crate::grid::{COLS, PLAY_BOTTOM, PLAY_TOP};
+use crate::player::{Move, MoveOutcome};
+use crate::snapshot::GameSnapshot;
+
+const ROWS: c_int = (PLAY_BOTTOM - PLAY_TOP + 1) as c_int;
+
+/// Opaque game handle.
+pub struct TetrisGame {
+    game: Game,
+    table: CommandTable,
+    /// Player who still has to choose a special action, if any.
+    awaiting_special: Option<i32>,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_error(msg: String) {
+    let msg = CString::new(msg.replace('\0', " ")).unwrap_or_default();
+    LAST_ERROR.with(|e| *e.borrow_mut() = Some(msg));
+}
+
+/// Runs an exported function's body; a panic becomes `tetris_last_error` and
+/// `failed` is returned instead of unwinding into the caller.
+fn guard<T>(failed: T, body: impl FnOnce() -> T) -> T {
+    match panic::catch_unwind(AssertUnwindSafe(body)) {
+        Ok(v) => v,
+        Err(payload) => {
+            let msg = payload.downcast_ref::<&str>().map(|s| s.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "unknown panic".to_string());
+            set_error(format!("internal error: {}", msg));
+            failed
+        }
+    }
+}
+
+fn to_c_string(s: String) -> *mut c_char {
+    CString::new(s.replace('\0', " ")).unwrap_or_default().into_raw()
+}
+
+impl TetrisGame {
+    fn new(args: &str) -> Result<Self, String> {
+        let args: Vec<String> = args.split_whitespace().map(str::to_string).collect();
+        let cfg = parse_args(&args)?;
+        let mut game = cfg.new_game()?;
+        game.quiet = true;
+        Ok(Self { game, table: CommandTable::new(), awaiting_special: None })
+    }
+
+    fn snapshot(&self) -> GameSnapshot {
+        let mut snap = self.game.snapshot();
+        snap.awaiting_special = self.awaiting_special;
+        snap
+    }
+
+    fn next_token(&mut self, rest: &mut VecDeque<String>) -> Option<String> {
+        self.table.pending.pop().or_else(|| rest.pop_front())
+    }
+
+    /// Runs a line of text-mode commands, stopping at the first error.
+    fn run_line(&mut self, line: &str) -> Result<(), String> {
+        let mut rest: VecDeque<String> = line.split_whitespace().map(str::to_string).collect();
+        self.table.pending.clear();
+        self.table.reset_expansions();
+        let res = (|| {
+            while let Some(token) = self.next_token(&mut rest) {
+                self.run_token(&token, &mut rest)?;
+            }
+            Ok(())
+        })();
+        self.table.pending.clear();
+        res
+    }
+
+    fn run_token(&mut self, token: &str, rest: &mut VecDeque<String>) -> Result<(), String> {
+        if let Some(player) = self.awaiting_special {
+            let block = if token == "force" { self.next_token(rest) } else { None };
+            let action = SpecialAction::parse(token, block.as_deref()).ok_or_else(|| {
+                format!("player {} must choose a special action: blind, heavy or force <I/J/L/S/T/O/Z>", player)
+            })?;
+            let param = action.param();
+            self.game.apply_special_action(player, action.name(), param.as_deref());
+            self.awaiting_special = None;
+            if self.game.running {
+                self.game.end_turn(player);
+            }
+            return Ok(());
+        }
+
+        let (repeat, command) = self.table.parse_command_token(token)?;
+        if self.table.is_macro_name(&command) {
+            let args: Vec<String> = (0..self.table.macro_arity(&command)).map_while(|_| self.next_token(rest)).collect();
+            let seq = self.table.expand_macro(&command, &args)?;
+            return self.table.push_expansion(&command, &seq, repeat);
+        }
+        if command == "restart" {
+            self.awaiting_special = None;
+            return self.game.restart();
+        }
+        if !self.game.running {
+            return Err("the game is over".to_string());
+        }
+
+        let player = self.game.current_player;
+        match command.as_str() {
+            "left" | "right" | "down" | "cw" | "ccw" | "drop" => {
+                let mv = Move::from_command(&command).unwrap();
+                if self.game.apply_moves(player, mv, repeat) == MoveOutcome::Landed && self.game.running {
+                    if self.game.finish_drop(player) {
+                        self.awaiting_special = Some(player);
+                    } else {
+                        self.game.end_turn(player);
+                    }
+                }
+            }
+            "levelup" | "leveldown" => {
+                let step = if command == "levelup" { repeat } else { -repeat };
+                let lvl = self.game.player(player).level.number();
+                let target = lvl.saturating_add(step).clamp(0, self.game.levels.max_level());
+                if target != lvl {
+                    self.game.set_level(player, target)?;
+                }
+            }
+            "I" | "J" | "L" | "S" | "T" | "O" | "Z" => {
+                let kind = BlockKind::from_char(command.chars().next().unwrap()).unwrap();
+                if let Err(e) = self.game.player_mut(player).force_replace_current(kind) {
+                    self.game.running = false;
+                    self.game.loser = Some(player);
+                    return Err(e);
+                }
+            }
+            "random" | "norandom" => {
+                let level = &mut self.game.player_mut(player).level;
+                if command == "random" {
+                    level.set_random(true)?;
+                } else {
+                    let file = rest.pop_front().ok_or("Missing file name for norandom")?;
+                    level.load_sequence(&file).and_then(|_| level.set_random(false))?;
+                }
+            }
+            "rename" => {
+                let (Some(new_name), Some(old_name)) = (rest.pop_front(), rest.pop_front()) else {
+                    return Err("Usage: rename <newname> <existingcommand>".to_string());
+                };
+                self.table.define_alias(&new_name, &old_name)?;
+            }
+            "macro" => {
+                let name = rest.pop_front().ok_or("Usage: macro <name> <sequence-of-commands>")?;
+                self.table.define_macro(&name, rest.drain(..).collect())?;
+            }
+            "unalias" | "unmacro" | "save-bindings" => {
+                let arg = rest.pop_front().ok_or_else(|| format!("Usage: {} <name>", command))?;
+                match command.as_str() {
+                    "unalias" => self.table.remove_alias(&arg)?,
+                    "unmacro" => self.table.remove_macro(&arg)?,
+                    _ => self.table.save_bindings(&arg)?,
+                };
+            }
+            _ => return Err(format!("{} is not available through the C API", command)),
+        }
+        Ok(())
+    }
+}
+
+/// Creates a game from command-line style flags (`-seed`, `-startlevel`,
+/// `-scriptfile1`, `-scriptfile2`); NULL means the defaults. Returns NULL on
+/// error.
+///
+/// # Safety
+/// `args` must be NULL or a valid NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn tetris_new(args: *const c_char) -> *mut TetrisGame {
+    guard(ptr::null_mut(), || {
+        let args = if args.is_null() { "" } else {
+            match CStr::from_ptr(args).to_str() {
+                Ok(s) => s,
+                Err(_) => {
+                    set_error("args are not valid UTF-8".to_string());
+                    return ptr::null_mut();
+                }
+            }
+        };
+        match TetrisGame::new(args) {
+            Ok(g) => Box::into_raw(Box::new(g)),
+            Err(e) => {
+                set_error(e);
+                ptr::null_mut()
+            }
+        }
+    })
+}
+
+/// Frees a game from `tetris_new`; NULL is ignored.
+#[no_mangle]
+pub extern "C" fn tetris_free(game: Option<Box<TetrisGame>>) {
+    guard((), || drop(game))
+}
+
+/// Runs a line of text-mode commands for the current player (`"3left cw drop"`,
+/// `"levelup"`, `"macro m left drop"`, ...). While a special action is pending
+/// the next command must be `blind`, `heavy` or `force <block>`. Returns 0, or
+/// -1 at the first command that fails (earlier ones stay applied).
+///
+/// # Safety
+/// `game` must be NULL or a live handle, and `line` NULL or a valid
+/// NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn tetris_command(game: Option<&mut TetrisGame>, line: *const c_char) -> c_int {
+    guard(-1, || {
+        let Some(game) = game else {
+            set_error("null game".to_string());
+            return -1;
+        };
+        if line.is_null() {
+            set_error("null command".to_string());
+            return -1;
+        }
+        let res = CStr::from_ptr(line)
+            .to_str()
+            .map_err(|_| "command is not valid UTF-8".to_string())
+            .and_then(|line| game.run_line(line));
+        match res {
+            Ok(()) => 0,
+            Err(e) => {
+                set_error(e);
+                -1
+            }
+        }
+    })
+}
+
+/// Message of the last failed call on this thread, or NULL. Owned by the library.
+#[no_mangle]
+pub extern "C" fn tetris_last_error() -> *const c_char {
+    LAST_ERROR.with(|e| e.borrow().as_ref().map_or(ptr::null(), |s| s.as_ptr()))
+}
+
+/// Player to move (1 or 2), or 0 for NULL.
+#[no_mangle]
+pub extern "C" fn tetris_current_player(game: Option<&TetrisGame>) -> c_int {
+    guard(0, || game.map_or(0, |g| g.game.current_player))
+}
+
+#[no_mangle]
+pub extern "C" fn tetris_running(game: Option<&TetrisGame>) -> bool {
+    guard(false, || game.is_some_and(|g| g.game.running))
+}
+
+/// Player who lost, or 0 while nobody has.
+#[no_mangle]
+pub extern "C" fn tetris_loser(game: Option<&TetrisGame>) -> c_int {
+    guard(0, || game.and_then(|g| g.game.loser).unwrap_or(0))
+}
+
+/// Player who has to choose a special action next, or 0.
+#[no_mangle]
+pub extern "C" fn tetris_awaiting_special(game: Option<&TetrisGame>) -> c_int {
+    guard(0, || game.and_then(|g| g.awaiting_special).unwrap_or(0))
+}
+
+/// Current score of `player`, or -1 for a bad player.
+#[no_mangle]
+pub extern "C" fn tetris_score(game: Option<&TetrisGame>, player: c_int) -> c_int {
+    guard(-1, || {
+        match game {
+            Some(g) if player == 1 || player == 2 => g.game.player(player).grid.cur_score(),
+            _ => -1,
+        }
+    })
+}
+
+/// Current level of `player`, or -1 for a bad player.
+#[no_mangle]
+pub extern "C" fn tetris_level(game: Option<&TetrisGame>, player: c_int) -> c_int {
+    guard(-1, || {
+        match game {
+            Some(g) if player == 1 || player == 2 => g.game.player(player).level.number(),
+            _ => -1,
+        }
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn tetris_hi_score(game: Option<&TetrisGame>) -> c_int {
+    guard(0, || game.map_or(0, |g| g.game.system_hi))
+}
+
+/// Rows in the play area, as read by `tetris_cell`.
+#[no_mangle]
+pub extern "C" fn tetris_rows() -> c_int {
+    ROWS
+}
+
+/// Columns in the play area.
+#[no_mangle]
+pub extern "C" fn tetris_cols() -> c_int {
+    COLS as c_int
+}
+
+/// Cell of `player`'s play area, `row` 0 at the top: `' '` when empty,
+/// otherwise the block letter (the falling block included). Returns 0 when out
+/// of range.
+#[no_mangle]
+pub extern "C" fn tetris_cell(game: Option<&TetrisGame>, player: c_int, row: c_int, col: c_int) -> c_char {
+    guard(0, || {
+        let Some(g) = game else { return 0 };
+        if !(player == 1 || player == 2) || !(0..ROWS).contains(&row) || !(0..COLS as c_int).contains(&col) {
+            return 0;
+        }
+        g.game.player(player).grid.get(PLAY_TOP + row as usize, col as usize) as c_char
+    })
+}
+
+/// Full state as JSON, in the same shape as the network `state` message.
+/// Free with `tetris_string_free`.
+#[no_mangle]
+pub extern "C" fn tetris_state_json(game: Option<&TetrisGame>) -> *mut c_char {
+    guard(ptr::null_mut(), || {
+        let Some(g) = game else { return ptr::null_mut() };
+        match serde_json::to_string(&g.snapshot()) {
+            Ok(s) => to_c_string(s),
+            Err(e) => {
+                set_error(e.to_string());
+                ptr::null_mut()
+            }
+        }
+    })
+}
+
+/// Events since the last call (clears, special actions, game over) as a JSON
+/// array. Free with `tetris_string_free`.
+#[no_mangle]
+pub extern "C" fn tetris_events_json(game: Option<&mut TetrisGame>) -> *mut c_char {
+    guard(ptr::null_mut(), || {
+        let Some(g) = game else { return ptr::null_mut() };
+        match serde_json::to_string(&g.game.take_events()) {
+            Ok(s) => to_c_string(s),
+            Err(e) => {
+                set_error(e.to_string());
+                ptr::null_mut()
+            }
+        }
+    })
+}
+
+/// Frees a string returned by this library; NULL is ignored.
+///
+/// # Safety
+/// `s` must be NULL or a string from `tetris_state_json` / `tetris_events_json`
+/// that has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn tetris_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
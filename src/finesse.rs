@@ -0,0 +1,76 @@
+use crate::block::{Block, BlockKind};
+use crate::grid::Grid;
+use crate::movegen::placements_for;
+use crate::player::Move;
+
+/// Inputs applied to the current falling block, plus what is needed to replay
+/// its options from the first input.
+#[derive(Debug, Clone)]
+pub struct PieceTrace {
+    start: Block,
+    heavy: i32,
+    horizontal_extra: i32,
+    pub inputs: Vec<Move>,
+}
+
+impl PieceTrace {
+    pub fn new(start: Block, heavy: i32, horizontal_extra: i32) -> Self {
+        Self { start, heavy, horizontal_extra, inputs: Vec::new() }
+    }
+}
+
+/// Finesse verdict for one placed piece.
+#[derive(Debug, Clone)]
+pub struct PieceFinesse {
+    /// 1-based index of the piece among this player's placements.
+    pub piece: u32,
+    pub kind: BlockKind,
+    pub inputs: Vec<Move>,
+    /// A shortest input path to the same lock position.
+    pub optimal: Vec<Move>,
+    /// Inputs spent beyond the optimal path.
+    pub faults: u32,
+}
+
+/// Compares the inputs in `trace` with the shortest path to where `locked`
+/// came to rest. `grid` is the board with `locked` still in it, before clears.
+/// Returns `None` if that position is not reachable from the trace's start
+/// (e.g. the level changed mid-piece).
+pub fn analyze(grid: &Grid, locked: &Block, trace: &PieceTrace, piece: u32) -> Option<PieceFinesse> {
+    let mut board = grid.clone();
+    locked.clear_from_grid(&mut board);
+    trace.start.write_to_grid(&mut board);
+
+    let mut target: Vec<(i32, i32)> = locked.cells.iter().map(|c| (c.r, c.c)).collect();
+    target.sort();
+
+    let best = placements_for(&board, &trace.start, trace.heavy, trace.horizontal_extra)
+        .into_iter()
+        .find(|pl| pl.cells() == target)?;
+
+    let faults = trace.inputs.len().saturating_sub(best.moves.len()) as u32;
+    Some(PieceFinesse {
+        piece,
+        kind: locked.kind,
+        inputs: trace.inputs.clone(),
+        optimal: best.moves,
+        faults,
+    })
+}
+
+fn join(moves: &[Move]) -> String {
+    moves.iter().map(|m| m.command()).collect::<Vec<_>>().join(" ")
+}
+
+/// Per-piece listing of the placements that wasted inputs, plus the game total.
+pub fn format_report(player: i32, records: &[PieceFinesse]) -> String {
+    let total: u32 = records.iter().map(|r| r.faults).sum();
+    let faulty: Vec<&PieceFinesse> = records.iter().filter(|r| r.faults > 0).collect();
+
+    let mut out = format!("Player {} finesse: {} fault(s) over {} piece(s)\n", player, total, records.len());
+    for r in faulty {
+        out.push_str(&format!("  #{:<3} {}  +{}  played: {}\n", r.piece, r.kind.to_char(), r.faults, join(&r.inputs)));
+        out.push_str(&format!("              optimal: {}\n", join(&r.optimal)));
+    }
+    out
+}
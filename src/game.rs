@@ -5,8 +5,10 @@ use rand::SeedableRng;
 
 use crate::block::{Block, BlockKind};
 use crate::effects::Effect;
-use crate::level::generate_level;
-use crate::player::PlayerState;
+use crate::events::GameEvent;
+use crate::level::{LevelSet, RuleAction};
+use crate::player::{Move, MoveOutcome, PlayerState};
+use crate::stats::PlayerStats;
 
 pub struct Game {
     pub rng: StdRng,
@@ -15,21 +17,38 @@ pub struct Game {
     pub p2: PlayerState,
     pub current_player: i32,
     pub running: bool,
+    /// Player whose block could not be placed, once the game is over.
+    pub loser: Option<i32>,
+    /// Suppresses the "Game over" announcement (headless runs).
+    pub quiet: bool,
+    /// Compare each placement's inputs with the shortest path (see `finesse`).
+    pub track_finesse: bool,
+    /// Clears, special actions, level changes and the game over, oldest first; see `take_events`.
+    pub events: Vec<GameEvent>,
 
     pub start_level: i32,
     pub script1: String,
     pub script2: String,
+    /// Level definitions; `levelup` stops at the last one.
+    pub levels: LevelSet,
+    /// Marathon mode: a player goes up a level after clearing this many lines.
+    pub lines_per_level: Option<i32>,
 }
 
 impl Game {
     pub fn new(seed: Option<u64>, start_level: i32, script1: String, script2: String) -> Result<Self, String> {
+        Self::with_levels(seed, start_level, script1, script2, LevelSet::builtin())
+    }
+
+    /// A game played with the given level definitions instead of the built-in ones.
+    pub fn with_levels(seed: Option<u64>, start_level: i32, script1: String, script2: String, levels: LevelSet) -> Result<Self, String> {
         let mut rng = match seed {
             Some(s) => StdRng::seed_from_u64(s),
             None => StdRng::seed_from_u64(12345),
         };
 
-        let p1 = PlayerState::new(start_level, &script1, &mut rng)?;
-        let p2 = PlayerState::new(start_level, &script2, &mut rng)?;
+        let p1 = PlayerState::new(&levels, start_level, &script1, &mut rng)?;
+        let p2 = PlayerState::new(&levels, start_level, &script2, &mut rng)?;
 
         Ok(Game {
             rng,
@@ -38,33 +57,125 @@ impl Game {
             p2,
             current_player: 1,
             running: true,
+            loser: None,
+            quiet: false,
+            track_finesse: true,
+            events: Vec::new(),
             start_level,
             script1,
             script2,
+            levels,
+            lines_per_level: None,
         })
     }
 
     pub fn restart(&mut self) -> Result<(), String> {
-        self.p1 = PlayerState::new(self.start_level, &self.script1, &mut self.rng)?;
-        self.p2 = PlayerState::new(self.start_level, &self.script2, &mut self.rng)?;
+        self.p1 = PlayerState::new(&self.levels, self.start_level, &self.script1, &mut self.rng)?;
+        self.p2 = PlayerState::new(&self.levels, self.start_level, &self.script2, &mut self.rng)?;
         self.current_player = 1;
         self.running = true;
+        self.loser = None;
+        self.events.clear();
         Ok(())
     }
 
+    /// Drains the events recorded since the last call.
+    pub fn take_events(&mut self) -> Vec<GameEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    pub fn player(&self, idx: i32) -> &PlayerState {
+        if idx == 1 { &self.p1 } else { &self.p2 }
+    }
+
+    pub fn player_mut(&mut self, idx: i32) -> &mut PlayerState {
+        if idx == 1 { &mut self.p1 } else { &mut self.p2 }
+    }
+
+    /// Applies one movement for `player_idx`, locking the block (clears,
+    /// scoring, next spawn) if it lands.
+    pub fn apply_move(&mut self, player_idx: i32, mv: Move) -> MoveOutcome {
+        if !self.running { return MoveOutcome::Blocked; }
+        let track = self.track_finesse;
+        let p = self.player_mut(player_idx);
+        p.stats.record_move();
+        if track {
+            p.trace_input(mv);
+        }
+        let outcome = p.step(mv);
+        if outcome == MoveOutcome::Landed {
+            if track {
+                p.finish_trace();
+            }
+            self.handle_landing(player_idx);
+        }
+        outcome
+    }
+
+    /// One row of gravity for `player_idx` (windowed play): the block falls a
+    /// row, or locks if it cannot. Not counted as an input, and a piece moved
+    /// by gravity is left out of finesse analysis.
+    pub fn apply_gravity(&mut self, player_idx: i32) -> MoveOutcome {
+        if !self.running { return MoveOutcome::Blocked; }
+        let p = self.player_mut(player_idx);
+        p.piece_trace = None;
+        if p.cur.move_down(&mut p.grid) {
+            return MoveOutcome::Moved;
+        }
+        self.handle_landing(player_idx);
+        MoveOutcome::Landed
+    }
+
+    /// Applies `mv` up to `repeat` times, stopping once the block lands or a
+    /// shift is blocked (rotations keep trying; heavy still pulls down).
+    pub fn apply_moves(&mut self, player_idx: i32, mv: Move, repeat: i32) -> MoveOutcome {
+        let mut outcome = MoveOutcome::Blocked;
+        for _ in 0..repeat {
+            if !self.running { break; }
+            outcome = self.apply_move(player_idx, mv);
+            match outcome {
+                MoveOutcome::Landed => break,
+                MoveOutcome::Blocked if matches!(mv, Move::Left | Move::Right | Move::Down) => break,
+                _ => {}
+            }
+        }
+        outcome
+    }
+
+    /// Post-drop bookkeeping after a landing: expires one-shot effects and
+    /// reports whether the player earned a special action (2+ rows cleared).
+    pub fn finish_drop(&mut self, player_idx: i32) -> bool {
+        let p = self.player_mut(player_idx);
+        p.on_drop_effects();
+        let earned = p.last_cleared >= 2;
+        self.running && earned
+    }
+
+    pub fn end_turn(&mut self, player_idx: i32) {
+        self.current_player = if player_idx == 1 { 2 } else { 1 };
+    }
+
+    /// Stats and score for both players, in player order.
+    pub fn stats_table(&self) -> Vec<(&PlayerStats, i32)> {
+        vec![
+            (&self.p1.stats, self.p1.grid.cur_score()),
+            (&self.p2.stats, self.p2.grid.cur_score()),
+        ]
+    }
+
     pub fn apply_special_action(&mut self, acting_player: i32, action: &str, param: Option<&str>) {
-        let (victim, victim_id) = if acting_player == 1 {
-            (&mut self.p2, 2)
+        let (actor, victim, victim_id) = if acting_player == 1 {
+            (&mut self.p1, &mut self.p2, 2)
         } else {
-            (&mut self.p1, 1)
+            (&mut self.p2, &mut self.p1, 1)
         };
 
         match action {
-            "blind" => {
-                victim.effects.push(Effect::blind());
-            }
-            "heavy" => {
-                victim.effects.push(Effect::heavy());
+            "blind" | "heavy" => {
+                victim.effects.push(if action == "blind" { Effect::blind() } else { Effect::heavy() });
+                actor.stats.specials_sent += 1;
+                victim.stats.specials_received += 1;
+                self.events.push(GameEvent::SpecialAction { player: acting_player, action: action.to_string(), block: None });
             }
             "force" => {
                 let Some(p) = param else {
@@ -85,10 +196,19 @@ impl Game {
                         eprintln!("force: invalid block type '*'");
                         return;
                     }
+                    self.events.push(GameEvent::SpecialAction { player: acting_player, action: action.to_string(), block: Some(t) });
+                    // only a block that made it onto the board counts as sent
                     if let Err(e) = victim.force_replace_current(kind) {
-                        println!("Game over, player {} lost.", victim_id);
-                        eprintln!("{}", e);
+                        if !self.quiet {
+                            println!("Game over, player {} lost.", victim_id);
+                            eprintln!("{}", e);
+                        }
                         self.running = false;
+                        self.loser = Some(victim_id);
+                        self.events.push(GameEvent::GameOver { loser: victim_id });
+                    } else {
+                        actor.stats.specials_sent += 1;
+                        victim.stats.specials_received += 1;
                     }
                 } else {
                     eprintln!("force: invalid block type '{}'", t);
@@ -109,9 +229,12 @@ impl Game {
             (&mut self.p2, &mut self.system_hi)
         };
 
+        p.stats.record_lock(p.cur.kind, p.grid.stack_height(), p.grid.count_holes());
+
         let mut block_loss: HashMap<i32, i32> = HashMap::new();
         let cleared = p.grid.check_and_clear(&mut block_loss);
         p.last_cleared = cleared;
+        p.stats.record_clear(cleared);
 
         if cleared > 0 {
             let lvl = p.level.number();
@@ -122,30 +245,54 @@ impl Game {
 
         p.apply_block_loss(&block_loss, system_hi);
 
-        p.level.notify_rows_cleared(cleared);
-        p.level.notify_block_placed();
-
-        // Level 4: star drop every 5 blocks without a clear
-        if p.level.should_drop_star() {
-            let star_id = p.next_block_id;
-            p.next_block_id += 1;
-
-            let mut star = Block::new(BlockKind::Star, star_id);
-            if star.can_spawn(&p.grid) {
-                p.register_block(star.id, p.level.number(), star.cells.len());
-                star.write_to_grid(&mut p.grid);
-                star.drop(&mut p.grid);
-
-                let mut star_loss: HashMap<i32, i32> = HashMap::new();
-                let extra = p.grid.check_and_clear(&mut star_loss);
-                if extra > 0 {
-                    let lvl = p.level.number();
-                    let delta = (extra + lvl) * (extra + lvl);
-                    p.grid.add_score(delta);
-                    *system_hi = (*system_hi).max(p.grid.cur_score());
+        p.level.on_rows_cleared(cleared);
+        let mut lines = cleared;
+        for action in p.level.on_block_placed() {
+            match action {
+                // Star drop after the level's number of blocks without a clear (5 at level 4)
+                RuleAction::DropStar { column } => {
+                    let star_id = p.next_block_id;
+                    p.next_block_id += 1;
+
+                    let mut star = Block::star(star_id, column);
+                    if star.can_spawn(&p.grid) {
+                        p.register_block(star.id, p.level.number(), star.cells.len());
+                        star.write_to_grid(&mut p.grid);
+                        star.drop(&mut p.grid);
+                        p.stats.stars_dropped += 1;
+
+                        let mut star_loss: HashMap<i32, i32> = HashMap::new();
+                        let extra = p.grid.check_and_clear(&mut star_loss);
+                        p.stats.record_clear(extra);
+                        if extra > 0 {
+                            let lvl = p.level.number();
+                            let delta = (extra + lvl) * (extra + lvl);
+                            p.grid.add_score(delta);
+                            *system_hi = (*system_hi).max(p.grid.cur_score());
+                        }
+                        p.apply_block_loss(&star_loss, system_hi);
+                        p.level.on_rows_cleared(extra);
+                        lines += extra;
+                    }
+                }
+            }
+        }
+
+        p.stats.record_settled(p.grid.count_holes());
+        if p.last_cleared > 0 {
+            self.events.push(GameEvent::LinesCleared { player: player_idx, lines: p.last_cleared });
+        }
+
+        // Marathon: the new level deals the block about to spawn
+        if let Some(per) = self.lines_per_level {
+            p.level_lines += lines;
+            let target = (p.level.number() + p.level_lines / per).min(self.levels.max_level());
+            p.level_lines %= per;
+            if target > p.level.number() {
+                match p.change_level(&self.levels, target, &mut self.rng) {
+                    Ok(()) => self.events.push(GameEvent::LevelChanged { player: player_idx, level: target }),
+                    Err(e) => eprintln!("level {}: {}", target, e),
                 }
-                p.apply_block_loss(&star_loss, system_hi);
-                p.level.notify_rows_cleared(extra);
             }
         }
 
@@ -153,10 +300,15 @@ impl Game {
         let new_id = p.next_block_id;
         p.next_block_id += 1;
 
-        let new_cur = Block::new(p.next_kind, new_id);
+        let kind = p.level.on_spawn(p.next_kind);
+        let new_cur = Block::new(kind, new_id);
         if !new_cur.can_spawn(&p.grid) {
-            println!("Game over, player {} lost.", player_idx);
+            if !self.quiet {
+                println!("Game over, player {} lost.", player_idx);
+            }
             self.running = false;
+            self.loser = Some(player_idx);
+            self.events.push(GameEvent::GameOver { loser: player_idx });
             return;
         }
 
@@ -170,11 +322,10 @@ impl Game {
         p.refresh_preview();
     }
 
+    /// Moves a player to `new_level`, clamped to the defined levels (see
+    /// `PlayerState::change_level` for what carries over).
     pub fn set_level(&mut self, player_idx: i32, new_level: i32) -> Result<(), String> {
         let p = if player_idx == 1 { &mut self.p1 } else { &mut self.p2 };
-        let lvl = new_level.clamp(0, 4);
-        p.level = generate_level(lvl, &p.script_file)?;
-        p.grid.set_level_digit(p.level.number());
-        Ok(())
+        p.change_level(&self.levels, new_level, &mut self.rng)
     }
 }
@@ -12,6 +12,12 @@ pub struct Grid {
     block_id: Vec<Vec<i32>>,
 }
 
+impl Default for Grid {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Grid {
     pub fn new() -> Self {
         let mut matrix = vec![vec![' '; COLS]; ROWS_TOTAL];
@@ -22,10 +28,8 @@ impl Grid {
         put_row(&mut matrix, 2, "Score:    0");
         put_row(&mut matrix, 3, "-----------");
 
-        for r in PLAY_TOP..=PLAY_BOTTOM {
-            for c in 0..COLS {
-                matrix[r][c] = ' ';
-            }
+        for row in &mut matrix[PLAY_TOP..=PLAY_BOTTOM] {
+            row.fill(' ');
         }
 
         put_row(&mut matrix, 22, "-----------");
@@ -76,6 +80,22 @@ impl Grid {
         &self.matrix
     }
 
+    /// FNV-1a hash of the play area (settled cells and the falling block),
+    /// stable across runs and platforms.
+    pub fn board_hash(&self) -> u64 {
+        let mut h: u64 = 0xcbf2_9ce4_8422_2325;
+        for row in &self.matrix[PLAY_TOP..=PLAY_BOTTOM] {
+            for &ch in row {
+                let mut buf = [0u8; 4];
+                for &b in ch.encode_utf8(&mut buf).as_bytes() {
+                    h ^= b as u64;
+                    h = h.wrapping_mul(0x0000_0100_0000_01b3);
+                }
+            }
+        }
+        h
+    }
+
     pub fn cur_score(&self) -> i32 {
         self.cur_score
     }
@@ -98,9 +118,11 @@ impl Grid {
         }
     }
 
+    /// Shows the level right-aligned in the header, like the score.
     pub fn set_level_digit(&mut self, lvl: i32) {
-        let d = char::from(b'0' + (lvl as u8));
-        self.matrix[1][10] = d;
+        for (i, ch) in (8..=10).zip(format!("{:>3}", lvl).chars()) {
+            self.matrix[1][i] = ch;
+        }
     }
 
     pub fn show_next(&mut self, preview_block_cells: &[(i32, i32, char)]) {
@@ -113,7 +135,7 @@ impl Grid {
 
         let base_r = 17; // maps 7->24, 8->25
         for (r, c, ch) in preview_block_cells {
-            let rr = (*r + base_r) as i32;
+            let rr = *r + base_r;
             let cc = *c;
             if rr >= 0 && (rr as usize) < self.matrix.len() && cc >= 0 && (cc as usize) < COLS {
                 self.matrix[rr as usize][cc as usize] = *ch;
@@ -121,6 +143,43 @@ impl Grid {
         }
     }
 
+    /// Height of the tallest column in the play area (0 for an empty board).
+    pub fn stack_height(&self) -> i32 {
+        for r in PLAY_TOP..=PLAY_BOTTOM {
+            if self.matrix[r].iter().any(|&ch| ch != ' ') {
+                return (PLAY_BOTTOM - r + 1) as i32;
+            }
+        }
+        0
+    }
+
+    /// Height of each play-area column, measured from the bottom row to its topmost filled cell.
+    pub fn column_heights(&self) -> [i32; COLS] {
+        let mut heights = [0; COLS];
+        for (c, height) in heights.iter_mut().enumerate() {
+            if let Some(r) = (PLAY_TOP..=PLAY_BOTTOM).find(|&r| self.matrix[r][c] != ' ') {
+                *height = (PLAY_BOTTOM - r + 1) as i32;
+            }
+        }
+        heights
+    }
+
+    /// Empty play-area cells that have a filled cell somewhere above them.
+    pub fn count_holes(&self) -> i32 {
+        let mut holes = 0;
+        for c in 0..COLS {
+            let mut covered = false;
+            for r in PLAY_TOP..=PLAY_BOTTOM {
+                if self.matrix[r][c] != ' ' {
+                    covered = true;
+                } else if covered {
+                    holes += 1;
+                }
+            }
+        }
+        holes
+    }
+
     pub fn check_and_clear(&mut self, block_loss: &mut HashMap<i32, i32>) -> i32 {
         let top = PLAY_TOP;
         let bottom = PLAY_BOTTOM;
@@ -1,95 +1,314 @@
 use rand::distributions::{Distribution, WeightedIndex};
 use rand::rngs::StdRng;
+use serde::{Deserialize, Serialize};
 
 use crate::block::BlockKind;
+use crate::grid::{COLS, PLAY_BOTTOM, PLAY_TOP};
 
+const PLAY_ROWS: usize = PLAY_BOTTOM - PLAY_TOP + 1;
+
+/// One level as described in a level file; see `LevelSet`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LevelDef {
+    pub randomizer: Randomizer,
+    /// Relative odds of T, S, Z, I, J, L, O for the `weighted` randomizer.
+    pub weights: [u32; 7],
+    /// Rows the block is pulled down after each move or rotation (at most the board height).
+    pub heavy: u32,
+    /// Drop a star block after this many pieces in a row without a clear.
+    pub star_interval: Option<u32>,
+    pub star_column: usize,
+    /// File for the `sequence` randomizer; defaults to the player's `-scriptfile`.
+    pub sequence_file: Option<String>,
+    /// Whether `random` / `norandom <file>` may switch a `weighted` level to a sequence file.
+    pub norandom: bool,
+    /// Windowed mode only: seconds between automatic one-row falls.
+    pub gravity: Option<f32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Randomizer {
+    /// Cycle through a sequence file.
+    Sequence,
+    /// Draw each block at random using `weights`.
+    Weighted,
+}
+
+impl Default for LevelDef {
+    fn default() -> Self {
+        Self {
+            randomizer: Randomizer::Weighted,
+            weights: [1; 7],
+            heavy: 0,
+            star_interval: None,
+            star_column: 5,
+            sequence_file: None,
+            norandom: false,
+            gravity: None,
+        }
+    }
+}
+
+/// The levels a game can be played at; level `n` is `levels[n]`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LevelSet {
+    pub levels: Vec<LevelDef>,
+}
+
+impl Default for LevelSet {
+    fn default() -> Self {
+        Self::builtin()
+    }
+}
+
+impl LevelSet {
+    /// Levels 0-4 of the original game.
+    pub fn builtin() -> Self {
+        let weighted = |weights, heavy, norandom| LevelDef { weights, heavy, norandom, ..Default::default() };
+        Self {
+            levels: vec![
+                LevelDef { randomizer: Randomizer::Sequence, ..Default::default() },
+                weighted([2, 1, 1, 2, 2, 2, 2], 0, false),
+                weighted([1; 7], 0, false),
+                weighted([1, 2, 2, 1, 1, 1, 1], 1, true),
+                LevelDef { star_interval: Some(5), ..weighted([1, 2, 2, 1, 1, 1, 1], 1, true) },
+            ],
+        }
+    }
+
+    /// Reads a JSON level file (`{"levels": [{...}, ...]}`).
+    pub fn load(path: &str) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("Cannot read level file {}: {}", path, e))?;
+        let set: LevelSet = serde_json::from_str(&text).map_err(|e| format!("Bad level file {}: {}", path, e))?;
+        set.validate().map_err(|e| format!("Bad level file {}: {}", path, e))?;
+        Ok(set)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.levels.is_empty() {
+            return Err("no levels defined".to_string());
+        }
+        for (n, def) in self.levels.iter().enumerate() {
+            if def.randomizer == Randomizer::Weighted && def.weights.iter().all(|&w| w == 0) {
+                return Err(format!("level {}: all weights are zero", n));
+            }
+            // more rows than the board is tall pulls no further (and must not overflow as i32)
+            if def.heavy as usize > PLAY_ROWS {
+                return Err(format!("level {}: heavy must be at most {}", n, PLAY_ROWS));
+            }
+            if def.star_interval.is_some_and(|i| i == 0 || i > i32::MAX as u32) {
+                return Err(format!("level {}: star_interval must be between 1 and {}", n, i32::MAX));
+            }
+            if def.star_column >= COLS {
+                return Err(format!("level {}: star_column must be below {}", n, COLS));
+            }
+            if def.gravity.is_some_and(|g| g.is_nan() || g <= 0.0) {
+                return Err(format!("level {}: gravity must be a positive number of seconds", n));
+            }
+            // a missing or empty file would otherwise only show up when a player reaches the level
+            if let Some(file) = &def.sequence_file {
+                Cycle::from_file(file).map_err(|e| format!("level {}: {}", n, e))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The highest level number.
+    pub fn max_level(&self) -> i32 {
+        self.levels.len() as i32 - 1
+    }
+
+    /// Builds level `number` (clamped to the defined levels) for a player
+    /// whose sequence file is `script_file`.
+    pub fn generate(&self, number: i32, script_file: &str) -> Result<Level, String> {
+        let number = number.clamp(0, self.max_level());
+        let def = &self.levels[number as usize];
+        let source = match def.randomizer {
+            Randomizer::Sequence => PieceSource::Sequence(Cycle::from_file(def.sequence_file.as_deref().unwrap_or(script_file))?),
+            Randomizer::Weighted => PieceSource::Weighted {
+                weights: def.weights.map(|w| w as usize).to_vec(),
+                norandom: def.norandom,
+                use_random: true,
+                seq: None,
+            },
+        };
+        let mut rules = Vec::new();
+        if let Some(interval) = def.star_interval {
+            rules.push(LevelRule::StarDrop { interval: interval as i32, column: def.star_column as i32, since_clear: 0 });
+        }
+        Ok(Level {
+            number,
+            heavy: def.heavy as i32,
+            gravity: def.gravity,
+            source,
+            rules,
+        })
+    }
+}
+
+/// A level: where its blocks come from plus the rules that react to play.
 #[derive(Clone)]
-pub enum Level {
-    Zero(Level0),
-    Random(RandomLevel),
-    Four(Level4),
+pub struct Level {
+    number: i32,
+    heavy: i32,
+    gravity: Option<f32>,
+    source: PieceSource,
+    rules: Vec<LevelRule>,
 }
 
 impl Level {
     pub fn number(&self) -> i32 {
-        match self {
-            Level::Zero(_) => 0,
-            Level::Random(r) => r.level,
-            Level::Four(f) => f.level,
-        }
+        self.number
     }
 
     pub fn is_heavy(&self) -> bool {
-        match self {
-            Level::Zero(_) => false,
-            Level::Random(r) => r.heavy,
-            Level::Four(f) => f.heavy,
-        }
+        self.heavy > 0
+    }
+
+    /// Rows the block is pulled down after each move or rotation.
+    pub fn heavy_rows(&self) -> i32 {
+        self.heavy
+    }
+
+    /// Seconds between automatic one-row falls in the windowed game.
+    pub fn gravity(&self) -> Option<f32> {
+        self.gravity
     }
 
     /// Peek: does NOT advance sequence pointer (except random)
     pub fn peek_kind(&mut self, rng: &mut StdRng) -> BlockKind {
-        match self {
-            Level::Zero(l0) => l0.peek(),
-            Level::Random(rl) => rl.peek(rng),
-            Level::Four(l4) => l4.peek(rng),
-        }
+        self.source.peek(rng)
     }
 
     /// Advance: advances sequence pointer (or samples again for random)
     pub fn advance_kind(&mut self, rng: &mut StdRng) -> BlockKind {
-        match self {
-            Level::Zero(l0) => l0.advance(),
-            Level::Random(rl) => rl.advance(rng),
-            Level::Four(l4) => l4.advance(rng),
+        self.source.advance(rng)
+    }
+
+    /// Takes over the player's progress from `old` when this freshly generated
+    /// level replaces it:
+    /// - a sequence level reading the same file keeps its position;
+    /// - a level that allows `norandom` keeps a loaded sequence and the
+    ///   `random` / `norandom` choice (other levels deal at random again);
+    /// - a rule keeps its counters if `old` had the same kind of rule, so
+    ///   pieces without a clear still count toward the next star.
+    ///
+    /// Rules attached with `add_rule` stay with the old level.
+    pub fn inherit(&mut self, old: &Level) {
+        match (&mut self.source, &old.source) {
+            (PieceSource::Sequence(order), PieceSource::Sequence(prev)) if order.file == prev.file => *order = prev.clone(),
+            (PieceSource::Weighted { norandom: true, use_random, seq, .. }, PieceSource::Weighted { use_random: prev_random, seq: prev_seq, .. }) => {
+                *use_random = *prev_random;
+                *seq = prev_seq.clone();
+            }
+            _ => {}
+        }
+        for rule in &mut self.rules {
+            if let Some(prev) = old.rules.iter().find(|r| std::mem::discriminant(*r) == std::mem::discriminant(rule)) {
+                rule.inherit(prev);
+            }
         }
     }
 
-    pub fn notify_rows_cleared(&mut self, cleared: i32) {
-        if let Level::Four(l4) = self {
-            l4.notify_rows_cleared(cleared);
+    /// Attaches another rule; it sees every event after the level's own rules.
+    pub fn add_rule(&mut self, rule: LevelRule) {
+        self.rules.push(rule);
+    }
+
+    /// A block of `kind` is entering the board; returns the kind the rules
+    /// spawn instead. Called exactly once per piece, so the preview shows the
+    /// kind before the rules have seen it.
+    pub fn on_spawn(&mut self, kind: BlockKind) -> BlockKind {
+        self.rules.iter_mut().fold(kind, |kind, rule| rule.on_spawn(kind))
+    }
+
+    pub fn on_rows_cleared(&mut self, cleared: i32) {
+        for rule in &mut self.rules {
+            rule.on_rows_cleared(cleared);
         }
     }
 
-    pub fn notify_block_placed(&mut self) {
-        if let Level::Four(l4) = self {
-            l4.notify_block_placed();
+    /// Called once a block has locked and its rows are cleared; returns what
+    /// the level's rules want done to the board.
+    pub fn on_block_placed(&mut self) -> Vec<RuleAction> {
+        self.rules.iter_mut().filter_map(LevelRule::on_block_placed).collect()
+    }
+
+    pub fn set_random(&mut self, val: bool) -> Result<(), String> {
+        match &mut self.source {
+            PieceSource::Weighted { norandom: true, use_random, .. } => { *use_random = val; Ok(()) }
+            _ => Err(format!("random/norandom is not available at level {}", self.number)),
         }
     }
 
-    pub fn should_drop_star(&self) -> bool {
-        match self {
-            Level::Four(l4) => l4.should_drop_star(),
-            _ => false,
+    pub fn load_sequence(&mut self, file: &str) -> Result<(), String> {
+        match &mut self.source {
+            PieceSource::Weighted { norandom: true, seq, .. } => { *seq = Some(Cycle::from_file(file)?); Ok(()) }
+            _ => Err(format!("norandom is not available at level {}", self.number)),
         }
     }
+}
 
-    pub fn set_random(&mut self, val: bool) -> Result<(), String> {
+/// Where a level's blocks come from.
+#[derive(Clone)]
+enum PieceSource {
+    /// Loop through a sequence file.
+    Sequence(Cycle),
+    /// Weighted random draws, or `seq` once `norandom <file>` has switched it off.
+    Weighted {
+        weights: Vec<usize>,
+        norandom: bool,
+        use_random: bool,
+        seq: Option<Cycle>,
+    },
+}
+
+impl PieceSource {
+    fn peek(&mut self, rng: &mut StdRng) -> BlockKind {
         match self {
-            Level::Random(rl) if rl.level >= 3 => { rl.use_random = val; Ok(()) }
-            Level::Four(l4) => { l4.use_random = val; Ok(()) }
-            Level::Random(_) => Err("random/norandom is only relevant in levels 3 and 4".to_string()),
-            Level::Zero(_) => Err("random/norandom is only relevant in levels 3 and 4".to_string()),
+            PieceSource::Sequence(order) => order.peek(),
+            PieceSource::Weighted { use_random: false, seq: Some(seq), .. } => seq.peek(),
+            PieceSource::Weighted { weights, .. } => sample(weights, rng),
         }
     }
 
-    pub fn load_sequence(&mut self, file: &str) -> Result<(), String> {
+    fn advance(&mut self, rng: &mut StdRng) -> BlockKind {
         match self {
-            Level::Random(rl) if rl.level >= 3 => rl.load_sequence(file),
-            Level::Four(l4) => l4.load_sequence(file),
-            _ => Err("norandom is only relevant in levels 3 and 4".to_string()),
+            PieceSource::Sequence(order) => order.advance(),
+            PieceSource::Weighted { use_random: false, seq: Some(seq), .. } => seq.advance(),
+            PieceSource::Weighted { weights, .. } => sample(weights, rng),
         }
     }
 }
 
+fn sample(weights: &[usize], rng: &mut StdRng) -> BlockKind {
+    // Index mapping matches the C++ builder order: 0 T, 1 S, 2 Z, 3 I, 4 J, 5 L, 6 O
+    let dist = WeightedIndex::new(weights).unwrap();
+    match dist.sample(rng) {
+        0 => BlockKind::T,
+        1 => BlockKind::S,
+        2 => BlockKind::Z,
+        3 => BlockKind::I,
+        4 => BlockKind::J,
+        5 => BlockKind::L,
+        6 => BlockKind::O,
+        _ => BlockKind::T,
+    }
+}
+
+/// A sequence file's blocks, looped forever.
 #[derive(Clone)]
-pub struct Level0 {
+struct Cycle {
+    file: String,
     order: Vec<BlockKind>,
     pos: usize,
 }
 
-impl Level0 {
-    pub fn from_file(file: &str) -> Result<Self, String> {
+impl Cycle {
+    fn from_file(file: &str) -> Result<Self, String> {
         let content = std::fs::read_to_string(file)
             .map_err(|_| format!("Cannot open sequence file: {}", file))?;
         let mut order = Vec::new();
@@ -105,193 +324,75 @@ impl Level0 {
         if order.is_empty() {
             return Err(format!("sequence file empty: {}", file));
         }
-        Ok(Level0 { order, pos: 0 })
+        Ok(Cycle { file: file.to_string(), order, pos: 0 })
     }
 
-    pub fn peek(&self) -> BlockKind {
+    fn peek(&self) -> BlockKind {
         self.order[self.pos]
     }
 
-    pub fn advance(&mut self) -> BlockKind {
+    fn advance(&mut self) -> BlockKind {
         self.pos = (self.pos + 1) % self.order.len();
         self.order[self.pos]
     }
 }
 
-#[derive(Clone)]
-pub struct RandomLevel {
-    pub level: i32,
-    pub heavy: bool,
-    weights: Vec<usize>,
-    pub use_random: bool,
-    seq: Vec<BlockKind>,
-    seq_pos: usize,
-}
-
-impl RandomLevel {
-    pub fn new(level: i32, heavy: bool, weights: Vec<usize>) -> Self {
-        Self {
-            level,
-            heavy,
-            weights,
-            use_random: true,
-            seq: Vec::new(),
-            seq_pos: 0,
-        }
-    }
-
-    fn sample(&self, rng: &mut StdRng) -> BlockKind {
-        // Index mapping matches the C++ builder order: 0 T, 1 S, 2 Z, 3 I, 4 J, 5 L, 6 O
-        let dist = WeightedIndex::new(self.weights.clone()).unwrap();
-        match dist.sample(rng) {
-            0 => BlockKind::T,
-            1 => BlockKind::S,
-            2 => BlockKind::Z,
-            3 => BlockKind::I,
-            4 => BlockKind::J,
-            5 => BlockKind::L,
-            6 => BlockKind::O,
-            _ => BlockKind::T,
-        }
-    }
-
-    pub fn load_sequence(&mut self, file: &str) -> Result<(), String> {
-        let content = std::fs::read_to_string(file)
-            .map_err(|_| format!("Cannot open sequence file: {}", file))?;
-        let mut seq = Vec::new();
-        for tok in content.split_whitespace() {
-            if tok.len() != 1 { continue; }
-            let ch = tok.chars().next().unwrap();
-            if let Some(k) = BlockKind::from_char(ch) {
-                if k != BlockKind::Star {
-                    seq.push(k);
-                }
-            }
-        }
-        if seq.is_empty() {
-            return Err(format!("sequence file empty: {}", file));
-        }
-        self.seq = seq;
-        self.seq_pos = 0;
-        Ok(())
-    }
-
-    pub fn peek(&mut self, rng: &mut StdRng) -> BlockKind {
-        if !self.use_random && !self.seq.is_empty() {
-            self.seq[self.seq_pos]
-        } else {
-            self.sample(rng)
-        }
-    }
-
-    pub fn advance(&mut self, rng: &mut StdRng) -> BlockKind {
-        if !self.use_random && !self.seq.is_empty() {
-            self.seq_pos = (self.seq_pos + 1) % self.seq.len();
-            self.seq[self.seq_pos]
-        } else {
-            self.sample(rng)
-        }
-    }
+/// What a rule asks the game to do after a block is placed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RuleAction {
+    /// Drop a 1x1 star block into `column`.
+    DropStar { column: i32 },
 }
 
+/// A mechanic attached to a level. New rules add a variant and whichever
+/// hooks they need; the rest pass through.
 #[derive(Clone)]
-pub struct Level4 {
-    pub level: i32,
-    pub heavy: bool,
-    weights: Vec<usize>,
-    pub use_random: bool,
-    seq: Vec<BlockKind>,
-    seq_pos: usize,
-    blocks_since_clear: i32,
+pub enum LevelRule {
+    /// A star every `interval` pieces placed without a clear (level 4).
+    StarDrop { interval: i32, column: i32, since_clear: i32 },
+    /// Every `interval`-th piece spawned is `kind`, whatever was dealt.
+    ForcePiece { interval: i32, kind: BlockKind, spawned: i32 },
 }
 
-impl Level4 {
-    pub fn new() -> Self {
-        Self {
-            level: 4,
-            heavy: true,
-            // same weights as C++ LevelFour
-            weights: vec![1, 2, 2, 1, 1, 1, 1],
-            use_random: true,
-            seq: Vec::new(),
-            seq_pos: 0,
-            blocks_since_clear: 0,
+impl LevelRule {
+    /// A new block of `kind` is entering the board; returns the kind to spawn instead.
+    pub fn on_spawn(&mut self, kind: BlockKind) -> BlockKind {
+        match self {
+            LevelRule::StarDrop { .. } => kind,
+            LevelRule::ForcePiece { interval, kind: forced, spawned } => {
+                *spawned += 1;
+                if *interval > 0 && *spawned % *interval == 0 { *forced } else { kind }
+            }
         }
     }
 
-    fn sample(&self, rng: &mut StdRng) -> BlockKind {
-        let dist = WeightedIndex::new(self.weights.clone()).unwrap();
-        match dist.sample(rng) {
-            0 => BlockKind::T,
-            1 => BlockKind::S,
-            2 => BlockKind::Z,
-            3 => BlockKind::I,
-            4 => BlockKind::J,
-            5 => BlockKind::L,
-            6 => BlockKind::O,
-            _ => BlockKind::T,
+    /// Copies the counters of `prev`, a rule of the same kind from the previous level.
+    pub fn inherit(&mut self, prev: &LevelRule) {
+        match (self, prev) {
+            (LevelRule::StarDrop { since_clear, .. }, LevelRule::StarDrop { since_clear: prev, .. }) => *since_clear = *prev,
+            (LevelRule::ForcePiece { spawned, .. }, LevelRule::ForcePiece { spawned: prev, .. }) => *spawned = *prev,
+            _ => {}
         }
     }
 
-    pub fn load_sequence(&mut self, file: &str) -> Result<(), String> {
-        let content = std::fs::read_to_string(file)
-            .map_err(|_| format!("Cannot open sequence file: {}", file))?;
-        let mut seq = Vec::new();
-        for tok in content.split_whitespace() {
-            if tok.len() != 1 { continue; }
-            let ch = tok.chars().next().unwrap();
-            if let Some(k) = BlockKind::from_char(ch) {
-                if k != BlockKind::Star {
-                    seq.push(k);
+    pub fn on_rows_cleared(&mut self, cleared: i32) {
+        match self {
+            LevelRule::StarDrop { since_clear, .. } => {
+                if cleared > 0 {
+                    *since_clear = 0;
                 }
             }
-        }
-        if seq.is_empty() {
-            return Err(format!("sequence file empty: {}", file));
-        }
-        self.seq = seq;
-        self.seq_pos = 0;
-        Ok(())
-    }
-
-    pub fn peek(&mut self, rng: &mut StdRng) -> BlockKind {
-        if !self.use_random && !self.seq.is_empty() {
-            self.seq[self.seq_pos]
-        } else {
-            self.sample(rng)
+            LevelRule::ForcePiece { .. } => {}
         }
     }
 
-    pub fn advance(&mut self, rng: &mut StdRng) -> BlockKind {
-        if !self.use_random && !self.seq.is_empty() {
-            self.seq_pos = (self.seq_pos + 1) % self.seq.len();
-            self.seq[self.seq_pos]
-        } else {
-            self.sample(rng)
-        }
-    }
-
-    pub fn notify_rows_cleared(&mut self, cleared: i32) {
-        if cleared > 0 {
-            self.blocks_since_clear = 0;
+    pub fn on_block_placed(&mut self) -> Option<RuleAction> {
+        match self {
+            LevelRule::StarDrop { interval, column, since_clear } => {
+                *since_clear += 1;
+                (*since_clear % *interval == 0).then_some(RuleAction::DropStar { column: *column })
+            }
+            LevelRule::ForcePiece { .. } => None,
         }
     }
-
-    pub fn notify_block_placed(&mut self) {
-        self.blocks_since_clear += 1;
-    }
-
-    pub fn should_drop_star(&self) -> bool {
-        self.blocks_since_clear > 0 && (self.blocks_since_clear % 5 == 0)
-    }
-}
-
-pub fn generate_level(level: i32, script_file: &str) -> Result<Level, String> {
-    match level {
-        1 => Ok(Level::Random(RandomLevel::new(1, false, vec![2, 1, 1, 2, 2, 2, 2]))),
-        2 => Ok(Level::Random(RandomLevel::new(2, false, vec![1, 1, 1, 1, 1, 1, 1]))),
-        3 => Ok(Level::Random(RandomLevel::new(3, true,  vec![1, 2, 2, 1, 1, 1, 1]))),
-        4 => Ok(Level::Four(Level4::new())),
-        _ => Ok(Level::Zero(Level0::from_file(script_file)?)),
-    }
 }
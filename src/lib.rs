@@ -5,5 +5,21 @@ pub mod effects;
 pub mod level;
 pub mod player;
 pub mod commands;
+pub mod script;
+pub mod repl;
 pub mod render_text;
 pub mod game;
+pub mod events;
+pub mod stats;
+pub mod batch;
+pub mod controller;
+pub mod sim;
+pub mod bot;
+pub mod movegen;
+pub mod finesse;
+pub mod protocol;
+pub mod snapshot;
+pub mod net;
+pub mod tournament;
+pub mod env;
+pub mod ffi;
@@ -1,7 +1,14 @@
-use tetris::config::parse_args;
+use tetris::config::{parse_args, Config};
 use tetris::game::Game;
 use tetris::grid::{COLS, PLAY_BOTTOM, PLAY_TOP};
 use tetris::block::BlockKind;
+use tetris::controller::Controller;
+use tetris::player::{Move, MoveOutcome};
+use tetris::net::{Client, Server, ServerMsg};
+use tetris::snapshot::{BoardView, GameSnapshot};
+use tetris::stats;
+
+use std::sync::mpsc::TryRecvError;
 
 use macroquad::prelude::*;
 
@@ -10,6 +17,8 @@ const ROWS_PLAY: usize = 18; // rows 4..21 inclusive
 const GAP: f32 = 80.0;
 const LEFT_MARGIN: f32 = 30.0;
 const TOP_MARGIN: f32 = 70.0;
+const BOT_STEP_SECS: f32 = 0.12; // pause between bot inputs so its moves are visible
+const EVENT_SECS: f64 = 3.0; // how long a network event stays on screen
 
 const BOARD_W: f32 = COLS as f32 * CELL;
 const BOARD_H: f32 = ROWS_PLAY as f32 * CELL;
@@ -58,12 +67,8 @@ fn color_for_char(ch: char) -> Color {
 }
 
 /// Compute phantom landing positions without mutating the block.
-/// Mirrors your C++ logic: treat a cell as blocking if it's not empty AND not owned by this same block id.
-fn compute_phantom_positions(
-    block_cells: &[(i32, i32)],
-    block_id: i32,
-    grid: &tetris::grid::Grid,
-) -> Vec<(i32, i32)> {
+/// A cell blocks if it's not empty AND not part of the falling block itself.
+fn compute_phantom_positions(block_cells: &[(i32, i32)], m: &[Vec<char>]) -> Vec<(i32, i32)> {
     let mut cells: Vec<(i32, i32)> = block_cells.to_vec();
 
     loop {
@@ -74,9 +79,8 @@ fn compute_phantom_positions(
                 can_down = false;
                 break;
             }
-            let ch = grid.get(nr as usize, c as usize);
-            let bid = grid.get_block_id(nr as usize, c as usize);
-            if ch != ' ' && bid != block_id {
+            let ch = m[nr as usize][c as usize];
+            if ch != ' ' && !block_cells.contains(&(nr, c)) {
                 can_down = false;
                 break;
             }
@@ -92,24 +96,8 @@ fn compute_phantom_positions(
     cells
 }
 
-fn draw_board(game: &Game, player: i32, offset_x: f32, offset_y: f32) {
-    let (g, cur_block, blind, lvl, score) = if player == 1 {
-        (
-            &game.p1.grid,
-            &game.p1.cur,
-            game.p1.has_blind(),
-            game.p1.level.number(),
-            game.p1.grid.cur_score(),
-        )
-    } else {
-        (
-            &game.p2.grid,
-            &game.p2.cur,
-            game.p2.has_blind(),
-            game.p2.level.number(),
-            game.p2.grid.cur_score(),
-        )
-    };
+fn draw_board(view: &BoardView, running: bool, offset_x: f32, offset_y: f32) {
+    let (blind, lvl, score, faults) = (view.blind, view.level, view.score, view.finesse_faults);
 
     // Board background
     draw_rectangle(
@@ -123,24 +111,19 @@ fn draw_board(game: &Game, player: i32, offset_x: f32, offset_y: f32) {
     // Top texts
     draw_text(&format!("Level: {}", lvl), offset_x, offset_y - 35.0, 24.0, BLACK);
     draw_text(&format!("Score: {}", score), offset_x, offset_y - 12.0, 24.0, BLACK);
+    let fin = format!("Finesse: {}", faults);
+    let fin_w = measure_text(&fin, None, 20, 1.0).width;
+    draw_text(&fin, offset_x + BOARD_W - fin_w, offset_y - 12.0, 20.0, DARKGRAY);
 
     // Border frame
     draw_rectangle_lines(offset_x, offset_y, BOARD_W, BOARD_H, 2.0, BLACK);
 
     // Draw cells (matrix rows 4..21)
-    let m = g.matrix();
-    for r in PLAY_TOP..=PLAY_BOTTOM {
-        for c in 0..COLS {
-            let mut ch = m[r][c];
-            let blind_cell = blind && in_blind_region(r, c);
-            if blind_cell {
-                ch = '?';
-            }
-
-            let mut col = if blind_cell { BLACK } else { color_for_char(ch) };
-            if ch == '?' {
-                col = BLACK;
-            }
+    let m = view.matrix();
+    for (r, row) in m.iter().enumerate().take(PLAY_BOTTOM + 1).skip(PLAY_TOP) {
+        for (c, &ch) in row.iter().enumerate().take(COLS) {
+            let ch = if blind && in_blind_region(r, c) { '?' } else { ch };
+            let col = if ch == '?' { BLACK } else { color_for_char(ch) };
 
             let x = offset_x + c as f32 * CELL;
             let y = offset_y + (r - PLAY_TOP) as f32 * CELL;
@@ -150,12 +133,11 @@ fn draw_board(game: &Game, player: i32, offset_x: f32, offset_y: f32) {
     }
 
     // Phantom block outline
-    if game.running {
-        let cells: Vec<(i32, i32)> = cur_block.cells.iter().map(|c| (c.r, c.c)).collect();
-        let phantom = compute_phantom_positions(&cells, cur_block.id, g);
+    if running {
+        let phantom = compute_phantom_positions(&view.cells, &m);
 
         for (r, c) in phantom {
-            if g.get(r as usize, c as usize) != ' ' {
+            if m[r as usize][c as usize] != ' ' {
                 continue;
             }
             let x = offset_x + c as f32 * CELL;
@@ -175,12 +157,16 @@ fn draw_board(game: &Game, player: i32, offset_x: f32, offset_y: f32) {
     // Next preview (matrix rows 24..25)
     let next_y = offset_y + BOARD_H + 35.0;
     draw_text("Next:", offset_x, next_y, 24.0, BLACK);
+    if !view.effects.is_empty() {
+        let fx = view.effects.join(" ");
+        let fx_w = measure_text(&fx, None, 20, 1.0).width;
+        draw_text(&fx, offset_x + BOARD_W - fx_w, next_y, 20.0, RED);
+    }
 
     let mini = CELL * 0.75;
     let mini_y0 = next_y + 10.0;
-    for rr in 24..=25 {
-        for c in 0..COLS {
-            let ch = m[rr][c];
+    for (rr, row) in m.iter().enumerate().skip(24).take(2) {
+        for (c, &ch) in row.iter().enumerate().take(COLS) {
             if ch == ' ' {
                 continue;
             }
@@ -192,6 +178,103 @@ fn draw_board(game: &Game, player: i32, offset_x: f32, offset_y: f32) {
     }
 }
 
+/// Draws both boards, the header and footer, and any special-action prompt.
+fn draw_scene(snap: &GameSnapshot, ui: UiMode) {
+    // Header
+    let hi = format!("Hi Score: {}", snap.hi_score);
+    let dim = measure_text(&hi, None, 28, 1.0);
+    draw_text(&hi, (WINDOW_W as f32 - dim.width) * 0.5, 24.0, 28.0, BLACK);
+
+    // Board positions
+    let b1x = LEFT_MARGIN;
+    let b2x = LEFT_MARGIN + BOARD_W + GAP;
+
+    draw_board(snap.board(1), snap.running, b1x, TOP_MARGIN);
+    draw_board(snap.board(2), snap.running, b2x, TOP_MARGIN);
+
+    // Footer
+    let cp = format!("Current player: {}", snap.current_player);
+    draw_text(&cp, LEFT_MARGIN, WINDOW_H as f32 - 18.0, 24.0, BLACK);
+
+    // Overlays
+    match ui {
+        UiMode::Playing => {}
+        UiMode::ChooseAction { acting_player } => {
+            let msg = format!(
+                "Player {}, choose special action: [B]lind / [H]eavy / [F]orce",
+                acting_player
+            );
+            draw_rectangle(
+                20.0,
+                WINDOW_H as f32 - 90.0,
+                WINDOW_W as f32 - 40.0,
+                60.0,
+                Color::new(1.0, 1.0, 1.0, 0.92),
+            );
+            draw_rectangle_lines(
+                20.0,
+                WINDOW_H as f32 - 90.0,
+                WINDOW_W as f32 - 40.0,
+                60.0,
+                2.0,
+                BLACK,
+            );
+            draw_text(&msg, 30.0, WINDOW_H as f32 - 52.0, 24.0, BLACK);
+        }
+        UiMode::ChooseForce { acting_player } => {
+            let msg = format!("Player {}, force block: press I/J/L/S/T/O/Z", acting_player);
+            draw_rectangle(
+                20.0,
+                WINDOW_H as f32 - 90.0,
+                WINDOW_W as f32 - 40.0,
+                60.0,
+                Color::new(1.0, 1.0, 1.0, 0.92),
+            );
+            draw_rectangle_lines(
+                20.0,
+                WINDOW_H as f32 - 90.0,
+                WINDOW_W as f32 - 40.0,
+                60.0,
+                2.0,
+                BLACK,
+            );
+            draw_text(&msg, 30.0, WINDOW_H as f32 - 52.0, 24.0, BLACK);
+        }
+    }
+}
+
+/// Red-framed message across the top of the window.
+fn draw_banner(msg: &str) {
+    draw_rectangle(20.0, 35.0, WINDOW_W as f32 - 40.0, 50.0, Color::new(1.0, 0.95, 0.95, 0.95));
+    draw_rectangle_lines(20.0, 35.0, WINDOW_W as f32 - 40.0, 50.0, 2.0, RED);
+    draw_text(msg, 30.0, 68.0, 24.0, BLACK);
+}
+
+fn key_to_move() -> Option<Move> {
+    let left = is_key_pressed(KeyCode::Left) || is_key_pressed(KeyCode::A);
+    let right = is_key_pressed(KeyCode::Right) || is_key_pressed(KeyCode::D);
+    let down = is_key_pressed(KeyCode::Down) || is_key_pressed(KeyCode::S);
+    let cw = is_key_pressed(KeyCode::E);
+    let ccw = is_key_pressed(KeyCode::Q);
+    let drop = is_key_pressed(KeyCode::Space);
+
+    if left {
+        Some(Move::Left)
+    } else if right {
+        Some(Move::Right)
+    } else if down {
+        Some(Move::Down)
+    } else if cw {
+        Some(Move::Cw)
+    } else if ccw {
+        Some(Move::Ccw)
+    } else if drop {
+        Some(Move::Drop)
+    } else {
+        None
+    }
+}
+
 fn key_to_block_kind() -> Option<BlockKind> {
     if is_key_pressed(KeyCode::I) {
         Some(BlockKind::I)
@@ -213,29 +296,172 @@ fn key_to_block_kind() -> Option<BlockKind> {
 }
 
 fn end_turn_or_prompt_special(game: &mut Game, ui: &mut UiMode, acting_player: i32) {
-    if acting_player == 1 {
-        game.p1.on_drop_effects();
-        if game.running && game.p1.last_cleared >= 2 {
-            *ui = UiMode::ChooseAction { acting_player };
-            return;
+    if game.finish_drop(acting_player) {
+        *ui = UiMode::ChooseAction { acting_player };
+        return;
+    }
+    game.end_turn(acting_player);
+}
+
+fn report_stats(game: &Game, cfg: &Config) {
+    let table = game.stats_table();
+    print!("{}", stats::format_report(&table));
+    if let Err(e) = stats::export(&table, cfg.stats_json.as_deref(), cfg.stats_csv.as_deref()) {
+        eprintln!("{}", e);
+    }
+}
+
+/// Starts a `net::Server` for `addr` on a background thread and returns the
+/// address a local client should connect to.
+fn spawn_server(addr: &str, cfg: &Config) -> Result<String, String> {
+    let mut game = cfg.new_game()?;
+    let mut controllers = cfg.build_controllers()?;
+    let mut server = Server::bind(addr).map_err(|e| format!("Cannot listen on {}: {}", addr, e))?;
+    server.set_spectator_delay(cfg.spectate_delay);
+    let port = server.local_addr().map_err(|e| e.to_string())?.port();
+    println!("Hosting on {}", addr);
+
+    game.quiet = true;
+    std::thread::spawn(move || {
+        if let Err(e) = server.run(&mut game, &mut controllers) {
+            eprintln!("server error: {}", e);
         }
-        game.current_player = 2;
-    } else {
-        game.p2.on_drop_effects();
-        if game.running && game.p2.last_cleared >= 2 {
-            *ui = UiMode::ChooseAction { acting_player };
+    });
+    Ok(format!("127.0.0.1:{}", port))
+}
+
+/// Plays (or watches) a networked game: the server owns the game, this
+/// window only draws the states it receives and sends key presses.
+async fn run_client(addr: &str, cfg: &Config) {
+    let mut client = match Client::connect(addr, cfg.name.as_deref(), cfg.spectate) {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("Cannot connect to {}: {}", addr, e);
             return;
         }
-        game.current_player = 1;
+    };
+    let me = client.seat;
+    let mut snap: Option<GameSnapshot> = None;
+    // latest announcement and when it arrived
+    let mut event: Option<(String, f64)> = None;
+    let mut ui = UiMode::Playing;
+    let mut closed = false;
+
+    loop {
+        loop {
+            match client.try_recv() {
+                Ok(ServerMsg::State { state }) => snap = Some(state),
+                Ok(ServerMsg::ChooseSpecial { player }) => ui = UiMode::ChooseAction { acting_player: player },
+                Ok(ServerMsg::Event { event: ev }) => event = Some((ev.describe(), get_time())),
+                Ok(ServerMsg::Error { message }) => eprintln!("server: {}", message),
+                Ok(ServerMsg::Welcome { .. }) => {}
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    closed = true;
+                    break;
+                }
+            }
+        }
+
+        clear_background(Color::new(0.97, 0.97, 0.97, 1.0));
+        if is_key_pressed(KeyCode::Escape) {
+            break;
+        }
+
+        let Some(s) = snap.as_ref() else {
+            let msg = if closed { "Server closed the connection" } else { "Waiting for players..." };
+            draw_text(msg, LEFT_MARGIN, TOP_MARGIN, 28.0, BLACK);
+            next_frame().await;
+            continue;
+        };
+
+        draw_scene(s, ui);
+        let seat = if me == 0 { "Spectating".to_string() } else { format!("You are player {}", me) };
+        let w = measure_text(&seat, None, 24, 1.0).width;
+        draw_text(&seat, WINDOW_W as f32 - LEFT_MARGIN - w, WINDOW_H as f32 - 18.0, 24.0, BLACK);
+        if let Some((msg, at)) = &event {
+            if ui == UiMode::Playing && get_time() - at < EVENT_SECS {
+                let w = measure_text(msg, None, 24, 1.0).width;
+                draw_text(msg, (WINDOW_W as f32 - w) * 0.5, WINDOW_H as f32 - 50.0, 24.0, DARKBLUE);
+            }
+        }
+
+        if !s.running || closed {
+            let msg = match s.loser {
+                Some(l) if !s.running => format!("Game Over — player {} lost. Esc to quit", l),
+                _ => "Disconnected — Esc to quit".to_string(),
+            };
+            draw_banner(&msg);
+            next_frame().await;
+            continue;
+        }
+
+        let sent = match ui {
+            UiMode::ChooseAction { .. } => {
+                if is_key_pressed(KeyCode::B) {
+                    ui = UiMode::Playing;
+                    client.send_special("blind", None)
+                } else if is_key_pressed(KeyCode::H) {
+                    ui = UiMode::Playing;
+                    client.send_special("heavy", None)
+                } else {
+                    if is_key_pressed(KeyCode::F) {
+                        ui = UiMode::ChooseForce { acting_player: me };
+                    }
+                    Ok(())
+                }
+            }
+            UiMode::ChooseForce { .. } => match key_to_block_kind() {
+                Some(kind) => {
+                    ui = UiMode::Playing;
+                    client.send_special("force", Some(&kind.to_char().to_string()))
+                }
+                None => Ok(()),
+            },
+            UiMode::Playing if me != 0 && s.current_player == me && s.awaiting_special.is_none() => match key_to_move() {
+                Some(mv) => client.send_move(mv, 1),
+                None => Ok(()),
+            },
+            UiMode::Playing => Ok(()),
+        };
+        if let Err(e) = sent {
+            eprintln!("Connection lost: {}", e);
+            closed = true;
+        }
+
+        next_frame().await;
     }
 }
 
 #[macroquad::main(window_conf)]
 async fn main() {
     let args: Vec<String> = std::env::args().skip(1).collect();
-    let cfg = parse_args(&args);
+    let cfg = match parse_args(&args) {
+        Ok(cfg) => cfg,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        }
+    };
 
-    let mut game = match Game::new(cfg.seed, cfg.level, cfg.script_file1.clone(), cfg.script_file2.clone()) {
+    if let Some(addr) = cfg.serve.as_deref() {
+        // host in the background and join our own game as a client
+        let addr = match spawn_server(addr, &cfg) {
+            Ok(a) => a,
+            Err(e) => {
+                eprintln!("{}", e);
+                return;
+            }
+        };
+        run_client(&addr, &cfg).await;
+        return;
+    }
+    if let Some(addr) = cfg.connect.as_deref() {
+        run_client(addr, &cfg).await;
+        return;
+    }
+
+    let mut game = match cfg.new_game() {
         Ok(g) => g,
         Err(e) => {
             eprintln!("Init error: {}", e);
@@ -243,85 +469,45 @@ async fn main() {
         }
     };
 
+    let mut controllers: [Option<Box<dyn Controller>>; 2] = match cfg.build_controllers() {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("{}", e);
+            return;
+        }
+    };
+    let mut bot_timer = 0.0;
+    // time since the current player's block last fell on its own (levels with gravity)
+    let mut gravity_timer = 0.0;
+
     let mut ui = UiMode::Playing;
+    // the summary for the current game has been printed/exported
+    let mut reported = false;
 
     loop {
         clear_background(Color::new(0.97, 0.97, 0.97, 1.0));
 
-        // Header
-        let hi = format!("Hi Score: {}", game.system_hi);
-        let dim = measure_text(&hi, None, 28, 1.0);
-        draw_text(&hi, (WINDOW_W as f32 - dim.width) * 0.5, 24.0, 28.0, BLACK);
-
-        // Board positions
-        let b1x = LEFT_MARGIN;
-        let b2x = LEFT_MARGIN + BOARD_W + GAP;
-
-        draw_board(&game, 1, b1x, TOP_MARGIN);
-        draw_board(&game, 2, b2x, TOP_MARGIN);
-
-        // Footer
-        let cp = format!("Current player: {}", game.current_player);
-        draw_text(&cp, LEFT_MARGIN, WINDOW_H as f32 - 18.0, 24.0, BLACK);
-
-        // Overlays
-        match ui {
-            UiMode::Playing => {}
-            UiMode::ChooseAction { acting_player } => {
-                let msg = format!(
-                    "Player {}, choose special action: [B]lind / [H]eavy / [F]orce",
-                    acting_player
-                );
-                draw_rectangle(
-                    20.0,
-                    WINDOW_H as f32 - 90.0,
-                    WINDOW_W as f32 - 40.0,
-                    60.0,
-                    Color::new(1.0, 1.0, 1.0, 0.92),
-                );
-                draw_rectangle_lines(
-                    20.0,
-                    WINDOW_H as f32 - 90.0,
-                    WINDOW_W as f32 - 40.0,
-                    60.0,
-                    2.0,
-                    BLACK,
-                );
-                draw_text(&msg, 30.0, WINDOW_H as f32 - 52.0, 24.0, BLACK);
-            }
-            UiMode::ChooseForce { acting_player } => {
-                let msg = format!("Player {}, force block: press I/J/L/S/T/O/Z", acting_player);
-                draw_rectangle(
-                    20.0,
-                    WINDOW_H as f32 - 90.0,
-                    WINDOW_W as f32 - 40.0,
-                    60.0,
-                    Color::new(1.0, 1.0, 1.0, 0.92),
-                );
-                draw_rectangle_lines(
-                    20.0,
-                    WINDOW_H as f32 - 90.0,
-                    WINDOW_W as f32 - 40.0,
-                    60.0,
-                    2.0,
-                    BLACK,
-                );
-                draw_text(&msg, 30.0, WINDOW_H as f32 - 52.0, 24.0, BLACK);
-            }
-        }
+        let snap = game.snapshot();
+        draw_scene(&snap, ui);
 
         // Game over overlay
         if !game.running {
-            let msg = "Game Over — press R to restart, Esc to quit";
-            draw_rectangle(20.0, 35.0, WINDOW_W as f32 - 40.0, 50.0, Color::new(1.0, 0.95, 0.95, 0.95));
-            draw_rectangle_lines(20.0, 35.0, WINDOW_W as f32 - 40.0, 50.0, 2.0, RED);
-            draw_text(msg, 30.0, 68.0, 24.0, BLACK);
+            if !reported {
+                report_stats(&game, &cfg);
+                reported = true;
+            }
+
+            draw_banner("Game Over — press R to restart, Esc to quit");
 
             if is_key_pressed(KeyCode::R) {
                 if let Err(e) = game.restart() {
                     eprintln!("restart error: {}", e);
                 }
                 ui = UiMode::Playing;
+                reported = false;
+                if let Ok(c) = cfg.build_controllers() {
+                    controllers = c;
+                }
             }
             if is_key_pressed(KeyCode::Escape) {
                 break;
@@ -332,11 +518,24 @@ async fn main() {
         }
 
         if is_key_pressed(KeyCode::Escape) {
+            report_stats(&game, &cfg);
             break;
         }
 
         // Special action modes
         match ui {
+            UiMode::ChooseAction { acting_player } if controllers[(acting_player - 1) as usize].is_some() => {
+                let ctrl = controllers[(acting_player - 1) as usize].as_mut().unwrap();
+                let action = ctrl.choose_special(&game, acting_player);
+                let param = action.param();
+                game.apply_special_action(acting_player, action.name(), param.as_deref());
+                ui = UiMode::Playing;
+                if game.running {
+                    game.end_turn(acting_player);
+                }
+                next_frame().await;
+                continue;
+            }
             UiMode::ChooseAction { acting_player } => {
                 if is_key_pressed(KeyCode::B) {
                     game.apply_special_action(acting_player, "blind", None);
@@ -369,10 +568,29 @@ async fn main() {
 
         // Restart
         if is_key_pressed(KeyCode::R) {
+            report_stats(&game, &cfg);
             if let Err(e) = game.restart() {
                 eprintln!("restart error: {}", e);
             }
             ui = UiMode::Playing;
+            if let Ok(c) = cfg.build_controllers() {
+                controllers = c;
+            }
+            next_frame().await;
+            continue;
+        }
+
+        // Computer turn: one input every BOT_STEP_SECS, keyboard ignored
+        let cp = game.current_player;
+        if let Some(ctrl) = controllers[(cp - 1) as usize].as_mut() {
+            bot_timer += get_frame_time();
+            if bot_timer >= BOT_STEP_SECS {
+                bot_timer = 0.0;
+                let mv = ctrl.next_move(&game, cp);
+                if game.apply_move(cp, mv) == MoveOutcome::Landed && game.running {
+                    end_turn_or_prompt_special(&mut game, &mut ui, cp);
+                }
+            }
             next_frame().await;
             continue;
         }
@@ -380,157 +598,37 @@ async fn main() {
         // Level up/down
         if is_key_pressed(KeyCode::PageUp) {
             let lvl = if game.current_player == 1 { game.p1.level.number() } else { game.p2.level.number() };
-            let _ = game.set_level(game.current_player, (lvl + 1).min(4));
+            if let Err(e) = game.set_level(game.current_player, (lvl + 1).min(game.levels.max_level())) {
+                eprintln!("levelup: {}", e);
+            }
         }
         if is_key_pressed(KeyCode::PageDown) {
             let lvl = if game.current_player == 1 { game.p1.level.number() } else { game.p2.level.number() };
-            let _ = game.set_level(game.current_player, (lvl - 1).max(0));
+            if let Err(e) = game.set_level(game.current_player, (lvl - 1).max(0)) {
+                eprintln!("leveldown: {}", e);
+            }
         }
 
         // Keyboard commands
-        let left = is_key_pressed(KeyCode::Left) || is_key_pressed(KeyCode::A);
-        let right = is_key_pressed(KeyCode::Right) || is_key_pressed(KeyCode::D);
-        let down = is_key_pressed(KeyCode::Down) || is_key_pressed(KeyCode::S);
-        let cw = is_key_pressed(KeyCode::E);
-        let ccw = is_key_pressed(KeyCode::Q);
-        let drop = is_key_pressed(KeyCode::Space);
-
         let acting_player = game.current_player;
-
-        if left {
-            let moved = if acting_player == 1 {
-                game.p1.cur.move_left(&mut game.p1.grid)
-            } else {
-                game.p2.cur.move_left(&mut game.p2.grid)
-            };
-            if moved {
-                let extra = if acting_player == 1 {
-                    (if game.p1.level.is_heavy() { 1 } else { 0 }) + game.p1.extra_heavy_after_horizontal()
-                } else {
-                    (if game.p2.level.is_heavy() { 1 } else { 0 }) + game.p2.extra_heavy_after_horizontal()
-                };
-                let mut forced = false;
-                for _ in 0..extra {
-                    let ok = if acting_player == 1 {
-                        game.p1.cur.move_down(&mut game.p1.grid)
-                    } else {
-                        game.p2.cur.move_down(&mut game.p2.grid)
-                    };
-                    if !ok {
-                        game.handle_landing(acting_player);
-                        forced = true;
-                        break;
-                    }
-                }
-                if forced && game.running {
-                    end_turn_or_prompt_special(&mut game, &mut ui, acting_player);
-                }
+        if let Some(mv) = key_to_move() {
+            if game.apply_move(acting_player, mv) == MoveOutcome::Landed && game.running {
+                gravity_timer = 0.0;
+                end_turn_or_prompt_special(&mut game, &mut ui, acting_player);
             }
-        } else if right {
-            let moved = if acting_player == 1 {
-                game.p1.cur.move_right(&mut game.p1.grid)
-            } else {
-                game.p2.cur.move_right(&mut game.p2.grid)
-            };
-            if moved {
-                let extra = if acting_player == 1 {
-                    (if game.p1.level.is_heavy() { 1 } else { 0 }) + game.p1.extra_heavy_after_horizontal()
-                } else {
-                    (if game.p2.level.is_heavy() { 1 } else { 0 }) + game.p2.extra_heavy_after_horizontal()
-                };
-                let mut forced = false;
-                for _ in 0..extra {
-                    let ok = if acting_player == 1 {
-                        game.p1.cur.move_down(&mut game.p1.grid)
-                    } else {
-                        game.p2.cur.move_down(&mut game.p2.grid)
-                    };
-                    if !ok {
-                        game.handle_landing(acting_player);
-                        forced = true;
-                        break;
-                    }
-                }
-                if forced && game.running {
+        }
+
+        // Gravity: the block falls a row every `gravity` seconds
+        if let Some(secs) = game.player(acting_player).level.gravity() {
+            gravity_timer += get_frame_time();
+            if gravity_timer >= secs && game.running && game.current_player == acting_player && ui == UiMode::Playing {
+                gravity_timer = 0.0;
+                if game.apply_gravity(acting_player) == MoveOutcome::Landed && game.running {
                     end_turn_or_prompt_special(&mut game, &mut ui, acting_player);
                 }
             }
-        } else if down {
-            if acting_player == 1 {
-                let ok1 = game.p1.cur.move_down(&mut game.p1.grid);
-                if !ok1 {
-                    if game.p1.level.is_heavy() {
-                        game.handle_landing(1);
-                        if game.running { end_turn_or_prompt_special(&mut game, &mut ui, 1); }
-                    }
-                } else if game.p1.level.is_heavy() {
-                    let ok2 = game.p1.cur.move_down(&mut game.p1.grid);
-                    if !ok2 {
-                        game.handle_landing(1);
-                        if game.running { end_turn_or_prompt_special(&mut game, &mut ui, 1); }
-                    }
-                }
-            } else {
-                let ok1 = game.p2.cur.move_down(&mut game.p2.grid);
-                if !ok1 {
-                    if game.p2.level.is_heavy() {
-                        game.handle_landing(2);
-                        if game.running { end_turn_or_prompt_special(&mut game, &mut ui, 2); }
-                    }
-                } else if game.p2.level.is_heavy() {
-                    let ok2 = game.p2.cur.move_down(&mut game.p2.grid);
-                    if !ok2 {
-                        game.handle_landing(2);
-                        if game.running { end_turn_or_prompt_special(&mut game, &mut ui, 2); }
-                    }
-                }
-            }
-        } else if cw {
-            if acting_player == 1 {
-                game.p1.cur.rotate_cw(&mut game.p1.grid);
-                if game.p1.level.is_heavy() {
-                    if !game.p1.cur.move_down(&mut game.p1.grid) {
-                        game.handle_landing(1);
-                        if game.running { end_turn_or_prompt_special(&mut game, &mut ui, 1); }
-                    }
-                }
-            } else {
-                game.p2.cur.rotate_cw(&mut game.p2.grid);
-                if game.p2.level.is_heavy() {
-                    if !game.p2.cur.move_down(&mut game.p2.grid) {
-                        game.handle_landing(2);
-                        if game.running { end_turn_or_prompt_special(&mut game, &mut ui, 2); }
-                    }
-                }
-            }
-        } else if ccw {
-            if acting_player == 1 {
-                game.p1.cur.rotate_ccw(&mut game.p1.grid);
-                if game.p1.level.is_heavy() {
-                    if !game.p1.cur.move_down(&mut game.p1.grid) {
-                        game.handle_landing(1);
-                        if game.running { end_turn_or_prompt_special(&mut game, &mut ui, 1); }
-                    }
-                }
-            } else {
-                game.p2.cur.rotate_ccw(&mut game.p2.grid);
-                if game.p2.level.is_heavy() {
-                    if !game.p2.cur.move_down(&mut game.p2.grid) {
-                        game.handle_landing(2);
-                        if game.running { end_turn_or_prompt_special(&mut game, &mut ui, 2); }
-                    }
-                }
-            }
-        } else if drop {
-            if acting_player == 1 {
-                game.p1.cur.drop(&mut game.p1.grid);
-                game.handle_landing(1);
-                if game.running { end_turn_or_prompt_special(&mut game, &mut ui, 1); }
-            } else {
-                game.p2.cur.drop(&mut game.p2.grid);
-                game.handle_landing(2);
-                if game.running { end_turn_or_prompt_special(&mut game, &mut ui, 2); }
-            }
+        } else {
+            gravity_timer = 0.0;
         }
 
         next_frame().await;
@@ -0,0 +1,84 @@
+use std::collections::{HashSet, VecDeque};
+
+use crate::block::Block;
+use crate::grid::Grid;
+use crate::player::{step_block, Move, MoveOutcome, PlayerState};
+
+/// A final resting position of the falling block and the shortest input path to it.
+#[derive(Debug, Clone)]
+pub struct Placement {
+    /// The block where it locks (same kind and id as the falling block).
+    pub block: Block,
+    pub moves: Vec<Move>,
+}
+
+impl Placement {
+    /// The input path in interpreter vocabulary, e.g. `["cw", "left", "left", "drop"]`.
+    pub fn commands(&self) -> Vec<&'static str> {
+        self.moves.iter().map(|m| m.command()).collect()
+    }
+
+    /// Locked cells as sorted `(row, col)` pairs.
+    pub fn cells(&self) -> Vec<(i32, i32)> {
+        cell_key(&self.block)
+    }
+}
+
+fn cell_key(b: &Block) -> Vec<(i32, i32)> {
+    let mut key: Vec<(i32, i32)> = b.cells.iter().map(|c| (c.r, c.c)).collect();
+    key.sort();
+    key
+}
+
+/// Every lock position the current block of `p` can reach, each with a minimal
+/// input path. Honors the level's heavy flag and active heavy effects, so
+/// positions that a forced pull would skip past are not reported.
+pub fn reachable_placements(p: &PlayerState) -> Vec<Placement> {
+    placements_for(&p.grid, &p.cur, p.level.heavy_rows(), p.extra_drop_after_horizontal())
+}
+
+/// Same as `reachable_placements`, for an arbitrary block on `grid`.
+/// `grid` must already contain `block`; the movement flags match `step_block`.
+pub fn placements_for(grid: &Grid, block: &Block, heavy: i32, horizontal_extra: i32) -> Vec<Placement> {
+    // Work on one copy of the board without the block; each probe writes the
+    // block in, steps it, and takes it back out, so nothing else is cloned.
+    let mut board = grid.clone();
+    block.clear_from_grid(&mut board);
+
+    let mut seen: HashSet<Vec<(i32, i32)>> = HashSet::new();
+    let mut locked: HashSet<Vec<(i32, i32)>> = HashSet::new();
+    let mut out: Vec<Placement> = Vec::new();
+    let mut queue: VecDeque<(Block, Vec<Move>)> = VecDeque::new();
+
+    seen.insert(cell_key(block));
+    queue.push_back((block.clone(), Vec::new()));
+
+    while let Some((state, path)) = queue.pop_front() {
+        for mv in Move::ALL {
+            let mut b = state.clone();
+            b.write_to_grid(&mut board);
+            let outcome = step_block(&mut board, &mut b, mv, heavy, horizontal_extra);
+            b.clear_from_grid(&mut board);
+
+            match outcome {
+                MoveOutcome::Blocked => {}
+                MoveOutcome::Moved => {
+                    if seen.insert(cell_key(&b)) {
+                        let mut next = path.clone();
+                        next.push(mv);
+                        queue.push_back((b, next));
+                    }
+                }
+                MoveOutcome::Landed => {
+                    // BFS order: the first path to reach a lock position is a shortest one
+                    if locked.insert(cell_key(&b)) {
+                        let mut moves = path.clone();
+                        moves.push(mv);
+                        out.push(Placement { block: b, moves });
+                    }
+                }
+            }
+        }
+    }
+    out
+}
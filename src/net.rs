@@ -0,0 +1,492 @@
+//! Networked versus over TCP: one process hosts the authoritative `Game`,
+//! players and spectators connect as clients.
+//!
+//! Messages are line-delimited JSON, like the bot protocol:
+//!
+//! ```text
+//! client -> {"type":"hello","name":"ann"}            (optional "spectate":true)
+//! server -> {"type":"welcome","player":1}            (0 = spectator)
+//! server -> {"type":"event","event":{...}}           (clears, special actions, game over)
+//! server -> {"type":"state","state":{...}}           (after every action, to everyone;
+//!                                                     a blinded player's own board is masked)
+//! client -> {"type":"move","command":"left","repeat":3}
+//! server -> {"type":"choose_special","player":1}     (only to the player who earned it)
+//! client -> {"type":"special","action":"force","block":"Z"}
+//! server -> {"type":"error","message":"not your turn"}
+//! ```
+//!
+//! Lines longer than `MAX_LINE` close the connection.
+//!
+//! Games are strictly 1v1: there are two seats. The first clients to say hello
+//! take the free ones (a seat reserved for a name only goes to the client with
+//! that name); later ones spectate.
+//! Play starts, with the first `state`, once every seat without a
+//! server-side controller is taken (and, for bot-only games, once the first
+//! spectator is watching).
+//! A player who disconnects mid-game forfeits. With a spectator delay
+//! (`Server::set_spectator_delay`) spectators get every event and state only
+//! that long after the players do.
+
+use std::collections::VecDeque;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender, TryRecvError};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::controller::{Controller, SpecialAction};
+use crate::events::GameEvent;
+use crate::game::Game;
+use crate::grid::{PLAY_BOTTOM, PLAY_TOP};
+use crate::player::{Move, MoveOutcome};
+use crate::sim;
+use crate::snapshot::GameSnapshot;
+
+pub const DEFAULT_PORT: u16 = 7777;
+/// How often the server checks for new connections while waiting for messages.
+const POLL: Duration = Duration::from_millis(20);
+/// Longest message line either side reads; a peer sending more is disconnected.
+pub const MAX_LINE: usize = 64 * 1024;
+/// Largest `repeat` a client may send; more can't move a block any further.
+pub const MAX_REPEAT: i32 = (PLAY_BOTTOM - PLAY_TOP + 1) as i32;
+
+fn one() -> i32 { 1 }
+
+/// Appends `DEFAULT_PORT` to an address given without one (`localhost`, `0.0.0.0`).
+pub fn with_default_port(addr: &str) -> String {
+    if addr.contains(':') { addr.to_string() } else { format!("{}:{}", addr, DEFAULT_PORT) }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClientMsg {
+    Hello {
+        #[serde(default)]
+        name: Option<String>,
+        #[serde(default)]
+        spectate: bool,
+    },
+    Move {
+        command: String,
+        #[serde(default = "one")]
+        repeat: i32,
+    },
+    Special {
+        action: String,
+        #[serde(default)]
+        block: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerMsg {
+    Welcome { player: i32 },
+    State { state: GameSnapshot },
+    Event { event: GameEvent },
+    ChooseSpecial { player: i32 },
+    Error { message: String },
+}
+
+fn write_msg<T: Serialize>(w: &mut impl Write, msg: &T) -> io::Result<()> {
+    let line = serde_json::to_string(msg).map_err(io::Error::other)?;
+    writeln!(w, "{}", line)?;
+    w.flush()
+}
+
+/// Forwards each line from `stream` through `wrap` to `tx`; `on_close` is sent once the stream ends.
+/// A line longer than `MAX_LINE` bytes closes the connection instead of being buffered.
+fn spawn_reader<T, F>(stream: TcpStream, tx: Sender<T>, wrap: F, on_close: Option<T>)
+where
+    T: Send + 'static,
+    F: Fn(&str) -> Option<T> + Send + 'static,
+{
+    std::thread::spawn(move || {
+        let mut reader = BufReader::new(stream);
+        let mut buf = Vec::new();
+        loop {
+            buf.clear();
+            match (&mut reader).take(MAX_LINE as u64 + 1).read_until(b'\n', &mut buf) {
+                Ok(0) | Err(_) => break,
+                Ok(_) => {}
+            }
+            if buf.len() > MAX_LINE && buf.last() != Some(&b'\n') {
+                let _ = reader.get_ref().shutdown(Shutdown::Both);
+                break;
+            }
+            let Ok(line) = std::str::from_utf8(&buf) else { break };
+            let line = line.trim_end_matches(['\n', '\r']);
+            if line.trim().is_empty() { continue; }
+            if let Some(item) = wrap(line) {
+                if tx.send(item).is_err() { return; }
+            }
+        }
+        if let Some(item) = on_close {
+            let _ = tx.send(item);
+        }
+    });
+}
+
+enum Event {
+    Msg(usize, ClientMsg),
+    Bad(usize, String),
+    Gone(usize),
+}
+
+struct Conn {
+    stream: TcpStream,
+    /// 1 or 2 for players, 0 for spectators, `None` before hello.
+    seat: Option<i32>,
+    name: Option<String>,
+    alive: bool,
+    /// Game updates held back from a spectator until they are due.
+    held: VecDeque<(Instant, ServerMsg)>,
+}
+
+impl Conn {
+    fn send(&mut self, msg: &ServerMsg) {
+        if self.alive && write_msg(&mut self.stream, msg).is_err() {
+            self.alive = false;
+        }
+    }
+
+    /// Sends an event or state, after `delay` if this is a spectator.
+    fn send_update(&mut self, msg: ServerMsg, delay: Duration) {
+        if self.seat == Some(0) && !delay.is_zero() {
+            self.held.push_back((Instant::now() + delay, msg));
+        } else {
+            self.send(&msg);
+        }
+    }
+
+    /// Sends the held updates that are due.
+    fn flush(&mut self) {
+        while let Some((due, _)) = self.held.front() {
+            if *due > Instant::now() { break; }
+            let (_, msg) = self.held.pop_front().unwrap();
+            self.send(&msg);
+        }
+    }
+}
+
+/// Hosts games for network clients.
+pub struct Server {
+    listener: TcpListener,
+    reserved: [Option<String>; 2],
+    spectator_delay: Duration,
+}
+
+impl Server {
+    pub fn bind(addr: &str) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        listener.set_nonblocking(true)?;
+        Ok(Self { listener, reserved: [None, None], spectator_delay: Duration::ZERO })
+    }
+
+    /// Holds every event and state back from spectators by `delay`, so a
+    /// spectator screen can't be used to peek at the opponent's board.
+    pub fn set_spectator_delay(&mut self, delay: Duration) {
+        self.spectator_delay = delay;
+    }
+
+    /// Keeps a seat for the client that says hello with that name (for the
+    /// next `run`); other clients can't take it.
+    pub fn reserve_seats(&mut self, names: [Option<String>; 2]) {
+        self.reserved = names;
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Plays `game` to the end. Seats with a controller are played by the
+    /// server itself; the others are taken by connecting clients.
+    pub fn run(&self, game: &mut Game, controllers: &mut [Option<Box<dyn Controller>>; 2]) -> io::Result<()> {
+        let (tx, rx) = mpsc::channel();
+        let human = [controllers[0].is_none(), controllers[1].is_none()];
+        let mut host = Host {
+            conns: Vec::new(),
+            tx,
+            pending: None,
+            started: false,
+            human,
+            reserved: self.reserved.clone(),
+            delay: self.spectator_delay,
+        };
+
+        while game.running {
+            host.accept(&self.listener)?;
+            host.flush();
+
+            if host.started && host.pending.is_none() {
+                let cp = game.current_player;
+                if let Some(ctrl) = controllers[(cp - 1) as usize].as_mut() {
+                    sim::play_turn(game, cp, ctrl.as_mut());
+                    host.broadcast(game);
+                    continue;
+                }
+            }
+
+            match rx.recv_timeout(POLL) {
+                Ok(ev) => host.handle(game, ev),
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+        }
+
+        host.broadcast(game);
+        // spectators still get the end of the game
+        while host.conns.iter().any(|c| c.alive && !c.held.is_empty()) {
+            std::thread::sleep(POLL);
+            host.flush();
+        }
+        for c in &host.conns {
+            let _ = c.stream.shutdown(Shutdown::Both);
+        }
+        Ok(())
+    }
+}
+
+struct Host {
+    conns: Vec<Conn>,
+    tx: Sender<Event>,
+    pending: Option<i32>,
+    started: bool,
+    human: [bool; 2],
+    reserved: [Option<String>; 2],
+    /// How long spectators' updates are held back.
+    delay: Duration,
+}
+
+impl Host {
+    fn accept(&mut self, listener: &TcpListener) -> io::Result<()> {
+        loop {
+            match listener.accept() {
+                Ok((stream, _)) => {
+                    stream.set_nonblocking(false)?;
+                    let id = self.conns.len();
+                    spawn_reader(
+                        stream.try_clone()?,
+                        self.tx.clone(),
+                        move |line| Some(match serde_json::from_str(line) {
+                            Ok(msg) => Event::Msg(id, msg),
+                            Err(e) => Event::Bad(id, format!("bad message '{}': {}", line, e)),
+                        }),
+                        Some(Event::Gone(id)),
+                    );
+                    self.conns.push(Conn { stream, seat: None, name: None, alive: true, held: VecDeque::new() });
+                }
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => return Ok(()),
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    fn flush(&mut self) {
+        for c in &mut self.conns {
+            c.flush();
+        }
+    }
+
+    fn seat_taken(&self, seat: i32) -> bool {
+        self.conns.iter().any(|c| c.alive && c.seat == Some(seat))
+    }
+
+    fn snapshot(&self, game: &Game) -> GameSnapshot {
+        let mut snap = game.snapshot();
+        snap.awaiting_special = self.pending;
+        snap
+    }
+
+    /// Sends pending events and the new state to every client.
+    fn broadcast(&mut self, game: &mut Game) {
+        let msgs: Vec<ServerMsg> = game.take_events().into_iter().map(|event| ServerMsg::Event { event }).collect();
+        let state = self.snapshot(game);
+        for c in self.conns.iter_mut() {
+            let Some(seat) = c.seat else { continue };
+            for msg in &msgs {
+                c.send_update(msg.clone(), self.delay);
+            }
+            c.send_update(ServerMsg::State { state: state.for_seat(seat) }, self.delay);
+        }
+        if let Some(p) = self.pending {
+            for c in self.conns.iter_mut().filter(|c| c.seat == Some(p)) {
+                c.send(&ServerMsg::ChooseSpecial { player: p });
+            }
+        }
+    }
+
+    /// Sends an error and the current state (and prompt) back to one client.
+    fn reject(&mut self, game: &Game, id: usize, message: &str) {
+        let state = self.snapshot(game);
+        let pending = self.pending;
+        let delay = self.delay;
+        let c = &mut self.conns[id];
+        c.send(&ServerMsg::Error { message: message.to_string() });
+        if let Some(seat) = c.seat {
+            c.send_update(ServerMsg::State { state: state.for_seat(seat) }, delay);
+            if let Some(p) = pending.filter(|&p| c.seat == Some(p)) {
+                c.send(&ServerMsg::ChooseSpecial { player: p });
+            }
+        }
+    }
+
+    fn handle(&mut self, game: &mut Game, ev: Event) {
+        match ev {
+            Event::Gone(id) => {
+                self.conns[id].alive = false;
+                if let Some(seat @ (1 | 2)) = self.conns[id].seat {
+                    if !game.quiet {
+                        println!("Player {} disconnected.", seat);
+                    }
+                    if self.started && game.running {
+                        game.running = false;
+                        game.loser = Some(seat);
+                        game.events.push(GameEvent::GameOver { loser: seat });
+                        self.broadcast(game);
+                    }
+                }
+            }
+            Event::Bad(id, e) => self.reject(game, id, &e),
+            Event::Msg(id, msg) => self.handle_msg(game, id, msg),
+        }
+    }
+
+    fn handle_msg(&mut self, game: &mut Game, id: usize, msg: ClientMsg) {
+        let seat = self.conns[id].seat;
+        match msg {
+            ClientMsg::Hello { name, spectate } => {
+                if seat.is_some() {
+                    return self.reject(game, id, "already joined");
+                }
+                let free = (1..=2).find(|&s| {
+                    let i = (s - 1) as usize;
+                    self.human[i] && !self.seat_taken(s) && self.reserved[i].as_ref().is_none_or(|r| name.as_ref() == Some(r))
+                });
+                let seat = if spectate { 0 } else { free.unwrap_or(0) };
+                let c = &mut self.conns[id];
+                c.seat = Some(seat);
+                c.name = name;
+                c.send(&ServerMsg::Welcome { player: seat });
+                if !game.quiet {
+                    let who = c.name.clone().unwrap_or_else(|| format!("client {}", id));
+                    if seat == 0 { println!("{} is spectating.", who); } else { println!("{} joined as player {}.", who, seat); }
+                }
+
+                // no state before the start, so each state a player sees answers exactly one action
+                if !self.started && (1..=2).all(|s| !self.human[(s - 1) as usize] || self.seat_taken(s)) {
+                    self.started = true;
+                    self.broadcast(game);
+                } else if self.started {
+                    let state = self.snapshot(game).for_seat(seat);
+                    self.conns[id].send_update(ServerMsg::State { state }, self.delay);
+                }
+            }
+            ClientMsg::Move { command, repeat } => {
+                let Some(mv) = Move::from_command(&command) else {
+                    return self.reject(game, id, &format!("unknown move '{}'", command));
+                };
+                let player = match seat {
+                    Some(p @ (1 | 2)) => p,
+                    _ => return self.reject(game, id, "spectators cannot move"),
+                };
+                if !self.started {
+                    return self.reject(game, id, "waiting for players");
+                }
+                if self.pending.is_some() || player != game.current_player {
+                    return self.reject(game, id, "not your turn");
+                }
+                if repeat > MAX_REPEAT {
+                    return self.reject(game, id, &format!("repeat must be at most {}", MAX_REPEAT));
+                }
+                if game.apply_moves(player, mv, repeat.max(1)) == MoveOutcome::Landed && game.running {
+                    if game.finish_drop(player) {
+                        self.pending = Some(player);
+                    } else {
+                        game.end_turn(player);
+                    }
+                }
+                self.broadcast(game);
+            }
+            ClientMsg::Special { action, block } => {
+                if seat.is_none() || self.pending != seat {
+                    return self.reject(game, id, "no special action to choose");
+                }
+                let player = seat.unwrap();
+                let Some(action) = SpecialAction::parse(&action, block.as_deref()) else {
+                    return self.reject(game, id, "choose blind, heavy or force <I/J/L/S/T/O/Z>");
+                };
+                let param = action.param();
+                game.apply_special_action(player, action.name(), param.as_deref());
+                self.pending = None;
+                if game.running {
+                    game.end_turn(player);
+                }
+                self.broadcast(game);
+            }
+        }
+    }
+}
+
+/// A connection to a `Server`, as a player or a spectator.
+pub struct Client {
+    stream: TcpStream,
+    incoming: Receiver<ServerMsg>,
+    /// Seat assigned by the server: 1 or 2, or 0 when spectating.
+    pub seat: i32,
+}
+
+impl Client {
+    /// Connects and waits for the server to assign a seat.
+    pub fn connect(addr: &str, name: Option<&str>, spectate: bool) -> io::Result<Self> {
+        let mut stream = TcpStream::connect(addr)?;
+        let (tx, incoming) = mpsc::channel();
+        spawn_reader(
+            stream.try_clone()?,
+            tx,
+            |line| match serde_json::from_str(line) {
+                Ok(msg) => Some(msg),
+                Err(e) => {
+                    eprintln!("server: bad message '{}': {}", line, e);
+                    None
+                }
+            },
+            None,
+        );
+
+        write_msg(&mut stream, &ClientMsg::Hello { name: name.map(str::to_string), spectate })?;
+        match incoming.recv() {
+            Ok(ServerMsg::Welcome { player }) => Ok(Self { stream, incoming, seat: player }),
+            Ok(other) => Err(io::Error::other(format!("expected welcome, got {:?}", other))),
+            Err(_) => Err(io::Error::other("server closed the connection")),
+        }
+    }
+
+    pub fn send_move(&mut self, mv: Move, repeat: i32) -> io::Result<()> {
+        write_msg(&mut self.stream, &ClientMsg::Move { command: mv.command().to_string(), repeat })
+    }
+
+    pub fn send_special(&mut self, action: &str, block: Option<&str>) -> io::Result<()> {
+        write_msg(&mut self.stream, &ClientMsg::Special {
+            action: action.to_string(),
+            block: block.map(str::to_string),
+        })
+    }
+
+    /// Next message from the server; `None` once the connection is closed.
+    pub fn recv(&mut self) -> Option<ServerMsg> {
+        self.incoming.recv().ok()
+    }
+
+    pub fn try_recv(&mut self) -> Result<ServerMsg, TryRecvError> {
+        self.incoming.try_recv()
+    }
+}
+
+impl Drop for Client {
+    fn drop(&mut self) {
+        // the reader thread holds a clone of the socket; shut it down so the server sees us leave
+        let _ = self.stream.shutdown(Shutdown::Both);
+    }
+}
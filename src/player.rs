@@ -2,8 +2,57 @@ use std::collections::HashMap;
 
 use crate::block::{Block, BlockKind};
 use crate::effects::Effect;
+use crate::finesse::{self, PieceFinesse, PieceTrace};
 use crate::grid::Grid;
-use crate::level::{generate_level, Level};
+use crate::level::{Level, LevelSet};
+use crate::stats::PlayerStats;
+
+/// The six piece-movement commands shared by every frontend.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Move {
+    Left,
+    Right,
+    Down,
+    Cw,
+    Ccw,
+    Drop,
+}
+
+impl Move {
+    pub const ALL: [Move; 6] = [Move::Left, Move::Right, Move::Down, Move::Cw, Move::Ccw, Move::Drop];
+
+    pub fn from_command(name: &str) -> Option<Self> {
+        match name {
+            "left" => Some(Move::Left),
+            "right" => Some(Move::Right),
+            "down" => Some(Move::Down),
+            "cw" => Some(Move::Cw),
+            "ccw" => Some(Move::Ccw),
+            "drop" => Some(Move::Drop),
+            _ => None,
+        }
+    }
+
+    pub fn command(self) -> &'static str {
+        match self {
+            Move::Left => "left",
+            Move::Right => "right",
+            Move::Down => "down",
+            Move::Cw => "cw",
+            Move::Ccw => "ccw",
+            Move::Drop => "drop",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MoveOutcome {
+    Moved,
+    /// Nothing changed (wall, stack, or failed rotation).
+    Blocked,
+    /// The block can no longer fall and must be locked.
+    Landed,
+}
 
 #[derive(Clone)]
 pub struct BlockInfo {
@@ -11,6 +60,7 @@ pub struct BlockInfo {
     pub cells_remaining: i32,
 }
 
+#[derive(Clone)]
 pub struct PlayerState {
     pub grid: Grid,
     pub level: Level,
@@ -22,18 +72,27 @@ pub struct PlayerState {
 
     pub effects: Vec<Effect>,
     pub last_cleared: i32,
+    /// Lines cleared toward the next marathon level (see `Game::lines_per_level`).
+    pub level_lines: i32,
 
     pub registry: HashMap<i32, BlockInfo>,
     pub next_block_id: i32,
+
+    pub stats: PlayerStats,
+
+    /// Inputs on the current block, for finesse analysis (None until its first input).
+    pub piece_trace: Option<PieceTrace>,
+    pub finesse: Vec<PieceFinesse>,
 }
 
 impl PlayerState {
-    pub fn new(start_level: i32, script_file: &str, rng: &mut rand::rngs::StdRng) -> Result<Self, String> {
+    pub fn new(levels: &LevelSet, start_level: i32, script_file: &str, rng: &mut rand::rngs::StdRng) -> Result<Self, String> {
         let mut grid = Grid::new();
-        let mut level = generate_level(start_level, script_file)?;
+        let mut level = levels.generate(start_level, script_file)?;
         grid.set_level_digit(level.number());
 
         let cur_kind = level.peek_kind(rng);
+        let cur_kind = level.on_spawn(cur_kind);
         let next_kind = level.advance_kind(rng);
 
         let mut next_block_id = 1;
@@ -57,8 +116,12 @@ impl PlayerState {
             start_level,
             effects: Vec::new(),
             last_cleared: 0,
+            level_lines: 0,
             registry: HashMap::new(),
             next_block_id,
+            stats: PlayerStats::new(),
+            piece_trace: None,
+            finesse: Vec::new(),
         };
 
         p.register_block(p.cur.id, p.level.number(), p.cur.cells.len());
@@ -68,6 +131,24 @@ impl PlayerState {
         Ok(p)
     }
 
+    /// Moves to level `number` (clamped to the defined levels), carrying over
+    /// what `Level::inherit` keeps. The falling block stays; the preview is
+    /// redrawn from the new level so the next block obeys its weights or
+    /// sequence. Staying on the same level changes nothing.
+    pub fn change_level(&mut self, levels: &LevelSet, number: i32, rng: &mut rand::rngs::StdRng) -> Result<(), String> {
+        let number = number.clamp(0, levels.max_level());
+        if number == self.level.number() {
+            return Ok(());
+        }
+        let mut level = levels.generate(number, &self.script_file)?;
+        level.inherit(&self.level);
+        self.level = level;
+        self.grid.set_level_digit(number);
+        self.next_kind = self.level.peek_kind(rng);
+        self.refresh_preview();
+        Ok(())
+    }
+
     pub fn has_blind(&self) -> bool {
         self.effects.iter().any(|e| e.is_blind())
     }
@@ -76,6 +157,43 @@ impl PlayerState {
         self.effects.iter().filter(|e| e.adds_heavy_on_horizontal()).count() as i32 * 2
     }
 
+    /// Rows the block is pulled down after a horizontal move: the level's
+    /// heavy rows plus two per active heavy effect.
+    pub fn extra_drop_after_horizontal(&self) -> i32 {
+        self.level.heavy_rows() + self.extra_heavy_after_horizontal()
+    }
+
+    /// Applies one movement to the falling block, including heavy-level and
+    /// heavy-effect pulls. Does not lock the block: on `Landed` the caller
+    /// must run `Game::handle_landing`.
+    pub fn step(&mut self, mv: Move) -> MoveOutcome {
+        let heavy = self.level.heavy_rows();
+        let extra = self.extra_drop_after_horizontal();
+        step_block(&mut self.grid, &mut self.cur, mv, heavy, extra)
+    }
+
+    /// Records one input on the current block, starting a trace on its first input.
+    pub fn trace_input(&mut self, mv: Move) {
+        if self.piece_trace.is_none() {
+            let heavy = self.level.heavy_rows();
+            let extra = self.extra_drop_after_horizontal();
+            self.piece_trace = Some(PieceTrace::new(self.cur.clone(), heavy, extra));
+        }
+        if let Some(t) = self.piece_trace.as_mut() {
+            t.inputs.push(mv);
+        }
+    }
+
+    /// Scores the traced inputs once the block has stopped (before rows clear).
+    pub fn finish_trace(&mut self) {
+        let Some(trace) = self.piece_trace.take() else { return };
+        let piece = self.stats.pieces_placed + 1;
+        if let Some(f) = finesse::analyze(&self.grid, &self.cur, &trace, piece) {
+            self.stats.finesse_faults += f.faults;
+            self.finesse.push(f);
+        }
+    }
+
     pub fn on_drop_effects(&mut self) {
         for e in &mut self.effects {
             e.on_drop();
@@ -133,9 +251,54 @@ impl PlayerState {
         }
 
         self.cur = new_block;
+        self.piece_trace = None;
         self.register_block(self.cur.id, self.level.number(), self.cur.cells.len());
         self.cur.write_to_grid(&mut self.grid);
 
         Ok(())
     }
 }
+
+/// Movement rules for one block on a grid that already contains it.
+///
+/// `heavy` is the number of rows added after rotations and soft drops (the
+/// level's heavy rows); `horizontal_extra` is the number of rows pulled down
+/// after a successful left/right (see `PlayerState::extra_drop_after_horizontal`).
+pub fn step_block(grid: &mut Grid, cur: &mut Block, mv: Move, heavy: i32, horizontal_extra: i32) -> MoveOutcome {
+    match mv {
+        Move::Left | Move::Right => {
+            let moved = if mv == Move::Left { cur.move_left(grid) } else { cur.move_right(grid) };
+            if !moved { return MoveOutcome::Blocked; }
+            for _ in 0..horizontal_extra {
+                if !cur.move_down(grid) {
+                    return MoveOutcome::Landed;
+                }
+            }
+            MoveOutcome::Moved
+        }
+        Move::Down => {
+            if !cur.move_down(grid) {
+                return if heavy > 0 { MoveOutcome::Landed } else { MoveOutcome::Blocked };
+            }
+            for _ in 0..heavy {
+                if !cur.move_down(grid) {
+                    return MoveOutcome::Landed;
+                }
+            }
+            MoveOutcome::Moved
+        }
+        Move::Cw | Move::Ccw => {
+            let rotated = if mv == Move::Cw { cur.rotate_cw(grid) } else { cur.rotate_ccw(grid) };
+            for _ in 0..heavy {
+                if !cur.move_down(grid) {
+                    return MoveOutcome::Landed;
+                }
+            }
+            if rotated || heavy > 0 { MoveOutcome::Moved } else { MoveOutcome::Blocked }
+        }
+        Move::Drop => {
+            cur.drop(grid);
+            MoveOutcome::Landed
+        }
+    }
+}
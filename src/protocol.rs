@@ -0,0 +1,359 @@
+//! Line-delimited JSON protocol for external bots (loosely after the Tetris Bot Protocol).
+//!
+//! The engine starts the bot as a child process and talks over its stdin/stdout,
+//! one JSON object per line:
+//!
+//! ```text
+//! engine -> {"type":"rules","player":2,"cols":11,"rows":18,"commands":["left",...]}
+//! bot    -> {"type":"ready","name":"mybot"}
+//! engine -> {"type":"suggest","id":1,"state":{...},"opponent":{...}}
+//! bot    -> {"type":"suggestion","id":1,"moves":["cw","left","left","drop"]}
+//! engine -> {"type":"special","id":2,"state":{...},"opponent":{...}}
+//! bot    -> {"type":"action","id":2,"action":"force","block":"Z"}
+//! engine -> {"type":"quit"}
+//! ```
+//!
+//! Replies must arrive within the timeout. A late, missing or malformed
+//! suggestion hard-drops the block where it is; a bad special answer becomes `heavy`.
+//! A reply echoing an older request's `id` (one that arrived after its
+//! timeout) is discarded; replies without an `id` are taken as they come.
+
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::block::{Block, BlockKind, Cell};
+use crate::controller::{Controller, SpecialAction};
+use crate::game::Game;
+use crate::grid::{Grid, COLS, PLAY_BOTTOM, PLAY_TOP};
+use crate::player::{Move, PlayerState};
+use crate::render_text::in_blind;
+
+pub const DEFAULT_TIMEOUT_MS: u64 = 1000;
+/// The bot gets at least this long to start up and answer `rules`.
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// One player's board as a bot sees it. Rows and columns are play-area
+/// coordinates: row 0 is the top playable row, row 17 the bottom.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BoardState {
+    /// Play-area rows without the falling block; `' '` is empty, `'?'` is hidden by blind.
+    pub board: Vec<String>,
+    pub current: char,
+    pub cells: Vec<(i32, i32)>,
+    pub next: Vec<char>,
+    pub level: i32,
+    /// The level pulls the block down after moves and rotations.
+    pub heavy: bool,
+    /// How many rows it pulls (older servers send only `heavy`).
+    #[serde(default)]
+    pub heavy_rows: i32,
+    pub effects: Vec<String>,
+    pub score: i32,
+}
+
+impl BoardState {
+    pub fn from_player(p: &PlayerState) -> Self {
+        let blind = p.has_blind();
+        let m = p.grid.matrix();
+        let own: Vec<(i32, i32)> = p.cur.cells.iter().map(|c| (c.r, c.c)).collect();
+
+        let board = (PLAY_TOP..=PLAY_BOTTOM)
+            .map(|r| {
+                m[r][..COLS].iter().enumerate().map(|(c, &ch)| {
+                    if blind && in_blind(r, c) {
+                        '?'
+                    } else if own.contains(&(r as i32, c as i32)) {
+                        ' '
+                    } else {
+                        ch
+                    }
+                }).collect::<String>()
+            })
+            .collect();
+
+        BoardState {
+            board,
+            current: p.cur.kind.to_char(),
+            cells: own.iter().map(|&(r, c)| (r - PLAY_TOP as i32, c)).collect(),
+            next: vec![p.next_kind.to_char()],
+            level: p.level.number(),
+            heavy: p.level.is_heavy(),
+            heavy_rows: p.level.heavy_rows(),
+            effects: p.effects.iter().map(|e| e.name().to_string()).collect(),
+            score: p.grid.cur_score(),
+        }
+    }
+
+    /// Rows the level pulls the block down after each move or rotation.
+    pub fn level_heavy_rows(&self) -> i32 {
+        self.heavy_rows.max(self.heavy as i32)
+    }
+
+    /// Rows pulled down after a left/right, as `PlayerState::extra_drop_after_horizontal`.
+    pub fn horizontal_extra(&self) -> i32 {
+        let heavy_effects = self.effects.iter().filter(|e| *e == "heavy").count() as i32;
+        self.level_heavy_rows() + heavy_effects * 2
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum EngineMsg {
+    Rules { player: i32, cols: usize, rows: usize, commands: Vec<String> },
+    Suggest { id: u64, state: BoardState, opponent: BoardState },
+    Special { id: u64, state: BoardState, opponent: BoardState },
+    Quit,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BotMsg {
+    Ready { name: Option<String> },
+    Suggestion {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        id: Option<u64>,
+        moves: Vec<String>,
+    },
+    Action {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        id: Option<u64>,
+        action: String,
+        block: Option<String>,
+    },
+}
+
+impl BotMsg {
+    /// The request this reply answers, if the bot echoed it.
+    fn id(&self) -> Option<u64> {
+        match self {
+            BotMsg::Ready { .. } => None,
+            BotMsg::Suggestion { id, .. } | BotMsg::Action { id, .. } => *id,
+        }
+    }
+}
+
+impl EngineMsg {
+    fn suggest(id: u64, game: &Game, player: i32) -> Self {
+        EngineMsg::Suggest {
+            id,
+            state: BoardState::from_player(game.player(player)),
+            opponent: BoardState::from_player(game.player(if player == 1 { 2 } else { 1 })),
+        }
+    }
+
+    fn special(id: u64, game: &Game, player: i32) -> Self {
+        EngineMsg::Special {
+            id,
+            state: BoardState::from_player(game.player(player)),
+            opponent: BoardState::from_player(game.player(if player == 1 { 2 } else { 1 })),
+        }
+    }
+}
+
+/// A bot running in a child process, driven through the protocol.
+pub struct ExternalBot {
+    child: Child,
+    stdin: ChildStdin,
+    lines: Receiver<String>,
+    timeout: Duration,
+    started: bool,
+    alive: bool,
+    plan: Vec<Move>,
+    planned_block: i32,
+    /// Id of the last `suggest` / `special` sent.
+    request: u64,
+    pub name: Option<String>,
+    /// Replies that were late, missing or malformed.
+    pub faults: u32,
+}
+
+impl ExternalBot {
+    /// Starts `command` through the shell (so `python3 bot.py` works).
+    pub fn spawn(command: &str, timeout: Duration) -> Result<Self, String> {
+        let mut child = Command::new("sh")
+            .arg("-c")
+            .arg(command)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("Cannot start bot '{}': {}", command, e))?;
+
+        let stdin = child.stdin.take().ok_or("bot stdin unavailable")?;
+        let stdout = child.stdout.take().ok_or("bot stdout unavailable")?;
+
+        // Reader thread so replies can be waited on with a timeout.
+        let (tx, lines) = mpsc::channel();
+        std::thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let Ok(line) = line else { break };
+                if line.trim().is_empty() { continue; }
+                if tx.send(line).is_err() { break; }
+            }
+        });
+
+        Ok(Self {
+            child,
+            stdin,
+            lines,
+            timeout,
+            started: false,
+            alive: true,
+            plan: Vec::new(),
+            planned_block: 0,
+            request: 0,
+            name: None,
+            faults: 0,
+        })
+    }
+
+    fn send(&mut self, msg: &EngineMsg) {
+        if !self.alive { return; }
+        // anything still queued answers a request that already timed out
+        while self.lines.try_recv().is_ok() {}
+        let line = serde_json::to_string(msg).unwrap_or_default();
+        if writeln!(self.stdin, "{}", line).and_then(|_| self.stdin.flush()).is_err() {
+            self.alive = false;
+        }
+    }
+
+    fn receive(&mut self, timeout: Duration) -> Option<BotMsg> {
+        // a bot that has exited still owes the reply
+        if !self.alive {
+            self.faults += 1;
+            return None;
+        }
+        let deadline = Instant::now() + timeout;
+        loop {
+            let left = deadline.saturating_duration_since(Instant::now());
+            return match self.lines.recv_timeout(left) {
+                Ok(line) => match serde_json::from_str::<BotMsg>(&line) {
+                    Ok(msg) if msg.id().is_some_and(|id| id != self.request) => {
+                        eprintln!("bot: dropping late reply to request {}", msg.id().unwrap_or_default());
+                        continue;
+                    }
+                    Ok(msg) => Some(msg),
+                    Err(e) => {
+                        eprintln!("bot: bad message '{}': {}", line, e);
+                        self.faults += 1;
+                        None
+                    }
+                },
+                Err(RecvTimeoutError::Timeout) => {
+                    eprintln!("bot: no reply within {} ms", timeout.as_millis());
+                    self.faults += 1;
+                    None
+                }
+                Err(RecvTimeoutError::Disconnected) => {
+                    eprintln!("bot: process closed its output");
+                    self.alive = false;
+                    self.faults += 1;
+                    None
+                }
+            };
+        }
+    }
+
+    fn handshake(&mut self, player: i32) {
+        self.started = true;
+        let commands = Move::ALL.iter().map(|m| m.command().to_string()).collect();
+        self.send(&EngineMsg::Rules { player, cols: COLS, rows: PLAY_BOTTOM - PLAY_TOP + 1, commands });
+        match self.receive(self.timeout.max(STARTUP_TIMEOUT)) {
+            Some(BotMsg::Ready { name }) => self.name = name,
+            Some(other) => {
+                eprintln!("bot: expected ready, got {:?}", other);
+                self.faults += 1;
+            }
+            None => {}
+        }
+    }
+}
+
+impl Controller for ExternalBot {
+    fn next_move(&mut self, game: &Game, player: i32) -> Move {
+        if !self.started {
+            self.handshake(player);
+        }
+        let cur_id = game.player(player).cur.id;
+        if cur_id != self.planned_block {
+            self.planned_block = cur_id;
+            self.request += 1;
+            self.send(&EngineMsg::suggest(self.request, game, player));
+            self.plan = match self.receive(self.timeout) {
+                Some(BotMsg::Suggestion { moves, .. }) => {
+                    let parsed: Option<Vec<Move>> = moves.iter().map(|m| Move::from_command(m)).collect();
+                    parsed.unwrap_or_else(|| {
+                        eprintln!("bot: invalid move in {:?}", moves);
+                        self.faults += 1;
+                        Vec::new()
+                    })
+                }
+                Some(other) => {
+                    eprintln!("bot: expected suggestion, got {:?}", other);
+                    self.faults += 1;
+                    Vec::new()
+                }
+                None => Vec::new(),
+            };
+            self.plan.reverse();
+        }
+        // an exhausted or forfeited plan drops the block where it is
+        self.plan.pop().unwrap_or(Move::Drop)
+    }
+
+    fn choose_special(&mut self, game: &Game, player: i32) -> SpecialAction {
+        self.request += 1;
+        self.send(&EngineMsg::special(self.request, game, player));
+        match self.receive(self.timeout) {
+            Some(BotMsg::Action { action, block, .. }) => {
+                SpecialAction::parse(&action, block.as_deref()).unwrap_or_else(|| {
+                    eprintln!("bot: invalid special action '{}'", action);
+                    self.faults += 1;
+                    SpecialAction::Heavy
+                })
+            }
+            Some(other) => {
+                eprintln!("bot: expected action, got {:?}", other);
+                self.faults += 1;
+                SpecialAction::Heavy
+            }
+            None => SpecialAction::Heavy,
+        }
+    }
+}
+
+impl Drop for ExternalBot {
+    fn drop(&mut self) {
+        self.send(&EngineMsg::Quit);
+        // give a well-behaved bot a moment to exit on its own
+        for _ in 0..10 {
+            if let Ok(Some(_)) = self.child.try_wait() { return; }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// Rebuilds a grid and falling block from a `BoardState` (for bots written in Rust).
+/// Hidden `'?'` cells are treated as filled.
+pub fn board_to_grid(state: &BoardState) -> Option<(Grid, Block)> {
+    let mut grid = Grid::new();
+    for (i, row) in state.board.iter().enumerate() {
+        for (c, ch) in row.chars().enumerate().take(COLS) {
+            if ch != ' ' {
+                grid.set_cell(PLAY_TOP + i, c, ch, 0);
+            }
+        }
+    }
+    let kind = BlockKind::from_char(state.current)?;
+    let cells = state.cells.iter()
+        .map(|&(r, c)| Cell { r: r + PLAY_TOP as i32, c, ch: state.current })
+        .collect();
+    let block = Block { kind, id: 1, cells };
+    block.write_to_grid(&mut grid);
+    Some((grid, block))
+}
@@ -1,8 +1,10 @@
 use crate::grid::Grid;
+use crate::snapshot::GameSnapshot;
 
 const GAP: usize = 5;
 
-fn in_blind(r: usize, c: usize) -> bool {
+/// Matrix cells hidden from a player under the blind effect.
+pub fn in_blind(r: usize, c: usize) -> bool {
     let blind_row_start = 9usize;
     let blind_row_end = 18usize;
     let blind_col_start = 2usize;
@@ -10,25 +12,33 @@ fn in_blind(r: usize, c: usize) -> bool {
     r >= blind_row_start && r <= blind_row_end && c >= blind_col_start && c <= blind_col_end
 }
 
-fn print_two_row(m1: &Vec<Vec<char>>, m2: &Vec<Vec<char>>, row: usize, blind1: bool, blind2: bool) {
-    for c in 0..11 {
-        let mut ch = m1[row][c];
-        if blind1 && in_blind(row, c) { ch = '?'; }
-        print!("{}", ch);
+fn print_two_row(m1: &[Vec<char>], m2: &[Vec<char>], row: usize, blind1: bool, blind2: bool) {
+    for (c, &ch) in m1[row].iter().enumerate().take(11) {
+        print!("{}", if blind1 && in_blind(row, c) { '?' } else { ch });
     }
     for _ in 0..GAP { print!(" "); }
-    for c in 0..11 {
-        let mut ch = m2[row][c];
-        if blind2 && in_blind(row, c) { ch = '?'; }
-        print!("{}", ch);
+    for (c, &ch) in m2[row].iter().enumerate().take(11) {
+        print!("{}", if blind2 && in_blind(row, c) { '?' } else { ch });
     }
     println!();
 }
 
 pub fn print_two_boards(g1: &Grid, g2: &Grid, blind1: bool, blind2: bool, hi_score: i32) {
-    let m1 = g1.matrix();
-    let m2 = g2.matrix();
+    print_two_matrices(g1.matrix(), g2.matrix(), blind1, blind2, hi_score);
+}
+
+/// Prints both boards of a snapshot (e.g. one received over the network).
+pub fn print_snapshot(snap: &GameSnapshot) {
+    let [b1, b2] = &snap.players;
+    print_two_matrices(&b1.matrix(), &b2.matrix(), b1.blind, b2.blind, snap.hi_score);
+    if !b1.effects.is_empty() || !b2.effects.is_empty() {
+        let effects = |v: &[String]| if v.is_empty() { "Effects: -".to_string() } else { format!("Effects: {}", v.join(",")) };
+        println!("{:<width$}{}", effects(&b1.effects), effects(&b2.effects), width = 11 + GAP);
+        println!();
+    }
+}
 
+fn print_two_matrices(m1: &[Vec<char>], m2: &[Vec<char>], blind1: bool, blind2: bool, hi_score: i32) {
     println!("\nHi Score: {}\n", hi_score);
 
     // mimic the C++: print rows 1..=3, then 4..=21, then 22..=25 (skip row0)
@@ -0,0 +1,106 @@
+//! Line editing for the text interpreter: arrow keys, history kept across
+//! sessions, and tab completion of command names (aliases and macros included).
+
+use std::io::IsTerminal;
+use std::path::PathBuf;
+
+use rustyline::completion::Completer;
+use rustyline::config::{CompletionType, Config};
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::DefaultHistory;
+use rustyline::validate::Validator;
+use rustyline::{Context, Editor, Helper};
+
+struct CommandHelper {
+    names: Vec<String>,
+}
+
+impl Completer for CommandHelper {
+    type Candidate = String;
+
+    /// Completes the word under the cursor, keeping a multiplier prefix (`3le` -> `3left`).
+    fn complete(&self, line: &str, pos: usize, _ctx: &Context<'_>) -> rustyline::Result<(usize, Vec<String>)> {
+        let start = line[..pos].rfind(char::is_whitespace).map_or(0, |i| i + 1);
+        let word = &line[start..pos];
+        let stem = word.trim_start_matches(|c: char| c.is_ascii_digit());
+        let mut matches: Vec<String> = self.names.iter().filter(|n| n.starts_with(stem)).cloned().collect();
+        matches.sort();
+        matches.dedup();
+        Ok((pos - stem.len(), matches))
+    }
+}
+
+impl Hinter for CommandHelper {
+    type Hint = String;
+}
+
+impl Highlighter for CommandHelper {}
+
+impl Validator for CommandHelper {}
+
+impl Helper for CommandHelper {}
+
+pub struct LineEditor {
+    editor: Editor<CommandHelper, DefaultHistory>,
+    history: Option<PathBuf>,
+}
+
+impl LineEditor {
+    /// An editor saving its history to `history`; `None` when stdin is not a
+    /// terminal, so piped input is read as plain lines.
+    pub fn new(history: Option<PathBuf>) -> Option<Self> {
+        if !std::io::stdin().is_terminal() {
+            return None;
+        }
+        let config = Config::builder().completion_type(CompletionType::List).build();
+        let mut editor = Editor::with_config(config).ok()?;
+        editor.set_helper(Some(CommandHelper { names: Vec::new() }));
+        if let Some(path) = &history {
+            let _ = editor.load_history(path);
+        }
+        Some(Self { editor, history })
+    }
+
+    /// Command names offered by tab completion.
+    pub fn set_completions(&mut self, names: &[String]) {
+        if let Some(helper) = self.editor.helper_mut() {
+            if helper.names != names {
+                helper.names = names.to_vec();
+            }
+        }
+    }
+
+    /// The next line, or `None` at end of input (Ctrl-D or Ctrl-C).
+    pub fn read_line(&mut self) -> Option<String> {
+        match self.editor.readline("> ") {
+            Ok(line) => {
+                if !line.trim().is_empty() {
+                    let _ = self.editor.add_history_entry(line.as_str());
+                }
+                Some(line)
+            }
+            Err(ReadlineError::Interrupted | ReadlineError::Eof) => None,
+            Err(e) => {
+                eprintln!("{}", e);
+                None
+            }
+        }
+    }
+}
+
+impl Drop for LineEditor {
+    fn drop(&mut self) {
+        if let Some(path) = &self.history {
+            if let Err(e) = self.editor.save_history(path) {
+                eprintln!("Cannot save history to {}: {}", path.display(), e);
+            }
+        }
+    }
+}
+
+/// `~/.tetris_history`.
+pub fn default_history_path() -> Option<PathBuf> {
+    std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".tetris_history"))
+}
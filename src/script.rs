@@ -0,0 +1,283 @@
+//! Control flow and variables for the text-mode interpreter.
+//!
+//! ```text
+//! set n 3
+//! repeat $n { left }            # also: repeat 4 { ... }, at most MAX_LOOP_ITERATIONS
+//! ${n}right drop                # ${name} expands inside a token
+//! if level>=3 { random }
+//! left while !blocked { left }  # stops after MAX_LOOP_ITERATIONS rounds
+//! set n $n+1
+//! ```
+//!
+//! Blocks are whitespace-separated `{ ... }` and may span lines, so they work in
+//! `sequence` files and macro bodies alike. Conditions and expressions are one
+//! token: terms are integers, `$variables` and the game facts below, joined by
+//! `+`/`-`; a condition compares two with `<`, `<=`, `>`, `>=`, `==` or `!=`, or
+//! is a single term that holds when non-zero (`!` negates it).
+//!
+//! Facts: `landed` / `blocked` (1 if the last movement command landed its
+//! block / changed nothing), `cleared` (rows cleared by the last drop), and
+//! `level`, `score` and `player` of the player to move.
+
+use std::collections::HashMap;
+
+use crate::commands::TokenStream;
+use crate::game::Game;
+use crate::player::MoveOutcome;
+
+/// Most rounds a loop may run: a `repeat` asking for more is refused, and a
+/// `while` is stopped with a warning once it gets there.
+pub const MAX_LOOP_ITERATIONS: u32 = 1000;
+
+/// Pushed after each loop body to run the next round; cannot be typed, since
+/// tokens never contain a newline.
+const LOOP_END: &str = "\nloop";
+
+const KEYWORDS: [&str; 6] = ["repeat", "if", "while", "set", "{", "}"];
+
+enum Round {
+    Repeat(u32),
+    While(String),
+}
+
+struct Loop {
+    round: Round,
+    body: Vec<String>,
+    iterations: u32,
+}
+
+#[derive(Default)]
+pub struct Script {
+    vars: HashMap<String, i32>,
+    loops: Vec<Loop>,
+    /// Player and outcome of the last movement command.
+    last_move: Option<(i32, MoveOutcome)>,
+}
+
+fn next_non_newline(stream: &mut TokenStream, pending: &mut Vec<String>) -> Option<String> {
+    loop {
+        let t = stream.next_token(pending)?;
+        if t != "\n" { return Some(t); }
+    }
+}
+
+/// Reads `{ ... }`, returning the tokens between the outer braces.
+fn read_block(keyword: &str, stream: &mut TokenStream, pending: &mut Vec<String>) -> Result<Vec<String>, String> {
+    match next_non_newline(stream, pending) {
+        Some(t) if t == "{" => {}
+        _ => return Err(format!("{}: expected '{{'", keyword)),
+    }
+    let mut body = Vec::new();
+    let mut depth = 0;
+    loop {
+        let t = next_non_newline(stream, pending).ok_or_else(|| format!("{}: missing '}}'", keyword))?;
+        match t.as_str() {
+            "{" => depth += 1,
+            "}" if depth == 0 => return Ok(body),
+            "}" => depth -= 1,
+            _ => {}
+        }
+        body.push(t);
+    }
+}
+
+fn push_round(pending: &mut Vec<String>, body: &[String]) {
+    pending.push(LOOP_END.to_string());
+    pending.extend(body.iter().rev().cloned());
+}
+
+fn is_name(s: &str) -> bool {
+    !s.is_empty() && s.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+impl Script {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether `token` is handled by `run` rather than the command table.
+    pub fn is_keyword(token: &str) -> bool {
+        token == LOOP_END || KEYWORDS.contains(&token)
+    }
+
+    /// Records a movement command, for `landed`, `blocked` and `cleared`.
+    pub fn note_move(&mut self, player: i32, outcome: MoveOutcome) {
+        self.last_move = Some((player, outcome));
+    }
+
+    /// Forgets loops in progress and the last move (after `restart`); variables are kept.
+    pub fn reset(&mut self) {
+        self.loops.clear();
+        self.last_move = None;
+    }
+
+    /// Forgets loops in progress whose remaining tokens were thrown away, as
+    /// when a macro expansion limit clears the pending stack.
+    pub fn drop_loops(&mut self) {
+        self.loops.clear();
+    }
+
+    pub fn var(&self, name: &str) -> Option<i32> {
+        self.vars.get(name).copied()
+    }
+
+    /// Expands `$name` (a whole token) and `${name}` (anywhere in a token).
+    pub fn substitute(&self, token: &str) -> Result<String, String> {
+        if !token.contains('$') {
+            return Ok(token.to_string());
+        }
+        if let Some(name) = token.strip_prefix('$').filter(|n| is_name(n)) {
+            return self.lookup(name).map(|v| v.to_string());
+        }
+        let mut out = String::new();
+        let mut rest = token;
+        while let Some(start) = rest.find("${") {
+            let end = rest[start..].find('}').ok_or_else(|| format!("Unclosed ${{ in {}", token))? + start;
+            out.push_str(&rest[..start]);
+            out.push_str(&self.lookup(&rest[start + 2..end])?.to_string());
+            rest = &rest[end + 1..];
+        }
+        out.push_str(rest);
+        if out.contains('$') {
+            return Err(format!("Use ${{name}} inside a token: {}", token));
+        }
+        Ok(out)
+    }
+
+    fn lookup(&self, name: &str) -> Result<i32, String> {
+        self.var(name).ok_or_else(|| format!("Unknown variable: ${}", name))
+    }
+
+    fn fact(&self, name: &str, game: &Game) -> Option<i32> {
+        let p = game.player(game.current_player);
+        Some(match name {
+            "landed" => matches!(self.last_move, Some((_, MoveOutcome::Landed))) as i32,
+            "blocked" => matches!(self.last_move, Some((_, MoveOutcome::Blocked))) as i32,
+            "cleared" => self.last_move.map_or(0, |(mover, _)| game.player(mover).last_cleared),
+            "level" => p.level.number(),
+            "score" => p.grid.cur_score(),
+            "player" => game.current_player,
+            _ => return None,
+        })
+    }
+
+    fn term(&self, term: &str, game: &Game) -> Result<i32, String> {
+        if let Ok(n) = term.parse() {
+            return Ok(n);
+        }
+        if let Some(name) = term.strip_prefix('$') {
+            return self.lookup(name);
+        }
+        self.fact(term, game).ok_or_else(|| format!("Unknown value: {}", term))
+    }
+
+    /// `a`, `a+b`, `$n-1`, ...
+    pub fn eval(&self, expr: &str, game: &Game) -> Result<i32, String> {
+        let mut total = 0i32;
+        let mut sign = 1;
+        let mut start = 0;
+        for (i, c) in expr.char_indices() {
+            // a sign at the start of a term belongs to it (`-3`)
+            if (c == '+' || c == '-') && i > start {
+                total = total.wrapping_add(self.term(&expr[start..i], game)?.wrapping_mul(sign));
+                sign = if c == '-' { -1 } else { 1 };
+                start = i + 1;
+            }
+        }
+        if start == expr.len() {
+            return Err(format!("Bad expression: '{}'", expr));
+        }
+        Ok(total.wrapping_add(self.term(&expr[start..], game)?.wrapping_mul(sign)))
+    }
+
+    pub fn condition(&self, cond: &str, game: &Game) -> Result<bool, String> {
+        if let Some(inner) = cond.strip_prefix('!').filter(|c| !c.starts_with('=')) {
+            return Ok(!self.condition(inner, game)?);
+        }
+        for op in ["<=", ">=", "==", "!=", "<", ">"] {
+            if let Some((a, b)) = cond.split_once(op) {
+                let (a, b) = (self.eval(a, game)?, self.eval(b, game)?);
+                return Ok(match op {
+                    "<=" => a <= b,
+                    ">=" => a >= b,
+                    "==" => a == b,
+                    "!=" => a != b,
+                    "<" => a < b,
+                    _ => a > b,
+                });
+            }
+        }
+        Ok(self.eval(cond, game)? != 0)
+    }
+
+    /// Runs a keyword, reading its arguments and block from the stream. Bodies
+    /// are pushed onto `pending` and run as ordinary tokens.
+    pub fn run(&mut self, keyword: &str, game: &Game, stream: &mut TokenStream, pending: &mut Vec<String>) -> Result<(), String> {
+        let mut arg = |what: &str| {
+            next_non_newline(stream, pending).ok_or_else(|| format!("{}: missing {}", keyword, what))
+        };
+        match keyword {
+            "set" => {
+                let name = arg("variable name")?;
+                if !is_name(&name) || name.chars().next().unwrap().is_ascii_digit() {
+                    return Err(format!("set: bad variable name '{}'", name));
+                }
+                let value = self.eval(&arg("value")?, game)?;
+                self.vars.insert(name, value);
+            }
+            "repeat" => {
+                let count = arg("count")?;
+                let n = self.eval(&count, game)?;
+                let body = read_block(keyword, stream, pending)?;
+                if n > MAX_LOOP_ITERATIONS as i32 {
+                    return Err(format!("repeat {}: more than {} iterations", count, MAX_LOOP_ITERATIONS));
+                }
+                if n > 0 && !body.is_empty() {
+                    push_round(pending, &body);
+                    self.loops.push(Loop { round: Round::Repeat(n as u32 - 1), body, iterations: 1 });
+                }
+            }
+            "if" => {
+                let cond = arg("condition")?;
+                let holds = self.condition(&cond, game)?;
+                let body = read_block(keyword, stream, pending)?;
+                if holds {
+                    pending.extend(body.into_iter().rev());
+                }
+            }
+            "while" => {
+                let cond = arg("condition")?;
+                let holds = self.condition(&cond, game)?;
+                let body = read_block(keyword, stream, pending)?;
+                if holds && !body.is_empty() {
+                    push_round(pending, &body);
+                    self.loops.push(Loop { round: Round::While(cond), body, iterations: 1 });
+                }
+            }
+            LOOP_END => {
+                let Some(mut lp) = self.loops.pop() else { return Ok(()) };
+                let again = match &lp.round {
+                    Round::Repeat(left) => *left > 0,
+                    Round::While(cond) => {
+                        let holds = self.condition(cond, game)?;
+                        if holds && lp.iterations >= MAX_LOOP_ITERATIONS {
+                            return Err(format!("while {}: stopped after {} iterations", cond, MAX_LOOP_ITERATIONS));
+                        }
+                        holds
+                    }
+                };
+                if again {
+                    if let Round::Repeat(left) = &mut lp.round {
+                        *left -= 1;
+                    }
+                    lp.iterations += 1;
+                    push_round(pending, &lp.body);
+                    self.loops.push(lp);
+                }
+            }
+            "{" | "}" => return Err(format!("Unexpected '{}'", keyword)),
+            _ => return Err(format!("Invalid command: {}", keyword)),
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,147 @@
+use crate::controller::Controller;
+use crate::game::Game;
+use crate::level::LevelSet;
+use crate::player::{Move, MoveOutcome};
+use crate::stats::PlayerStats;
+
+/// Inputs after which a piece is hard-dropped, so a wandering controller cannot stall a game.
+pub const MAX_INPUTS_PER_PIECE: u32 = 64;
+
+#[derive(Debug, Clone)]
+pub struct SimOptions {
+    pub seed: u64,
+    pub level: i32,
+    pub script1: String,
+    pub script2: String,
+    /// Stop after this many pieces in total (both players) if nobody has lost.
+    pub max_pieces: u32,
+    pub levels: LevelSet,
+}
+
+#[derive(Debug, Clone)]
+pub struct GameResult {
+    pub seed: u64,
+    pub level: i32,
+    /// Player who topped out, or `None` if the piece limit was reached first.
+    pub loser: Option<i32>,
+    pub pieces: u32,
+    pub scores: [i32; 2],
+    pub stats: [PlayerStats; 2],
+}
+
+impl GameResult {
+    /// Winner by survival, falling back to score when the piece limit ended the game.
+    pub fn winner(&self) -> Option<i32> {
+        match self.loser {
+            Some(l) => Some(if l == 1 { 2 } else { 1 }),
+            None if self.scores[0] > self.scores[1] => Some(1),
+            None if self.scores[1] > self.scores[0] => Some(2),
+            None => None,
+        }
+    }
+}
+
+/// Plays one piece for `player`: asks the controller for moves until the block
+/// lands, then runs the post-drop bookkeeping and passes the turn.
+pub fn play_turn(game: &mut Game, player: i32, ctrl: &mut dyn Controller) {
+    let mut inputs = 0;
+    while game.running {
+        let mv = if inputs >= MAX_INPUTS_PER_PIECE { Move::Drop } else { ctrl.next_move(game, player) };
+        inputs += 1;
+        if game.apply_move(player, mv) == MoveOutcome::Landed {
+            break;
+        }
+    }
+    if !game.running { return; }
+
+    if game.finish_drop(player) {
+        let action = ctrl.choose_special(game, player);
+        let param = action.param();
+        game.apply_special_action(player, action.name(), param.as_deref());
+    }
+    game.end_turn(player);
+}
+
+/// Runs a full headless game between two controllers.
+pub fn play_game(opts: &SimOptions, c1: &mut dyn Controller, c2: &mut dyn Controller) -> Result<GameResult, String> {
+    let mut game = Game::with_levels(Some(opts.seed), opts.level, opts.script1.clone(), opts.script2.clone(), opts.levels.clone())?;
+    game.quiet = true;
+    game.track_finesse = false;
+
+    let mut pieces = 0;
+    while game.running && pieces < opts.max_pieces {
+        let player = game.current_player;
+        if player == 1 {
+            play_turn(&mut game, 1, c1);
+        } else {
+            play_turn(&mut game, 2, c2);
+        }
+        pieces += 1;
+    }
+
+    Ok(GameResult {
+        seed: opts.seed,
+        level: opts.level,
+        loser: game.loser,
+        pieces,
+        scores: [game.p1.grid.cur_score(), game.p2.grid.cur_score()],
+        stats: [game.p1.stats.clone(), game.p2.stats.clone()],
+    })
+}
+
+/// One CSV row per game.
+pub fn results_csv(results: &[GameResult]) -> String {
+    let mut out = String::from("seed,level,winner,pieces,score1,score2,lines1,lines2,stars1,stars2,holes1,holes2\n");
+    for r in results {
+        out.push_str(&format!("{},{},{},{},{},{},{},{},{},{},{},{}\n",
+            r.seed, r.level,
+            r.winner().map(|w| w.to_string()).unwrap_or_default(),
+            r.pieces, r.scores[0], r.scores[1],
+            r.stats[0].lines_cleared, r.stats[1].lines_cleared,
+            r.stats[0].stars_dropped, r.stats[1].stars_dropped,
+            r.stats[0].holes_created, r.stats[1].holes_created));
+    }
+    out
+}
+
+fn percentile(sorted: &[i32], p: f64) -> i32 {
+    if sorted.is_empty() { return 0; }
+    let idx = ((sorted.len() - 1) as f64 * p).round() as usize;
+    sorted[idx]
+}
+
+/// Aggregate CSV, one row per level: score distribution (both players pooled),
+/// game length in pieces, and Level 4 star frequency (stars per placed piece).
+pub fn summary_csv(results: &[GameResult]) -> String {
+    let mut out = String::from("level,games,score_mean,score_min,score_p25,score_median,score_p75,score_max,\
+length_mean,length_min,length_max,topouts,stars_per_piece\n");
+
+    let mut levels: Vec<i32> = results.iter().map(|r| r.level).collect();
+    levels.sort();
+    levels.dedup();
+
+    for lvl in levels {
+        let games: Vec<&GameResult> = results.iter().filter(|r| r.level == lvl).collect();
+        let mut scores: Vec<i32> = games.iter().flat_map(|r| r.scores).collect();
+        scores.sort();
+        let lengths: Vec<u32> = games.iter().map(|r| r.pieces).collect();
+        let topouts = games.iter().filter(|r| r.loser.is_some()).count();
+        let stars: u32 = games.iter().map(|r| r.stats[0].stars_dropped + r.stats[1].stars_dropped).sum();
+        let placed: u32 = games.iter().map(|r| r.stats[0].pieces_placed + r.stats[1].pieces_placed).sum();
+
+        let score_mean = scores.iter().map(|&s| s as f64).sum::<f64>() / scores.len().max(1) as f64;
+        let length_mean = lengths.iter().map(|&l| l as f64).sum::<f64>() / lengths.len().max(1) as f64;
+        let stars_per_piece = if placed == 0 { 0.0 } else { stars as f64 / placed as f64 };
+
+        out.push_str(&format!("{},{},{:.2},{},{},{},{},{},{:.2},{},{},{},{:.4}\n",
+            lvl, games.len(), score_mean,
+            scores.first().copied().unwrap_or(0),
+            percentile(&scores, 0.25), percentile(&scores, 0.5), percentile(&scores, 0.75),
+            scores.last().copied().unwrap_or(0),
+            length_mean,
+            lengths.iter().min().copied().unwrap_or(0),
+            lengths.iter().max().copied().unwrap_or(0),
+            topouts, stars_per_piece));
+    }
+    out
+}
@@ -0,0 +1,94 @@
+use serde::{Deserialize, Serialize};
+
+use crate::game::Game;
+use crate::player::PlayerState;
+use crate::render_text::in_blind;
+
+/// Everything a frontend needs to draw one player's board.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BoardView {
+    /// Full display matrix (header, play area and preview), one string per row.
+    /// Cells under blind are only masked in `GameSnapshot::for_seat`; local
+    /// renderers apply `render_text::in_blind` themselves.
+    pub rows: Vec<String>,
+    /// Matrix cells of the falling block, as `(row, col)`.
+    pub cells: Vec<(i32, i32)>,
+    pub blind: bool,
+    pub level: i32,
+    pub score: i32,
+    pub finesse_faults: u32,
+    /// Active special-action effects (`blind`, `heavy`).
+    pub effects: Vec<String>,
+}
+
+impl BoardView {
+    pub fn from_player(p: &PlayerState) -> Self {
+        Self {
+            rows: p.grid.matrix().iter().map(|r| r.iter().collect()).collect(),
+            cells: p.cur.cells.iter().map(|c| (c.r, c.c)).collect(),
+            blind: p.has_blind(),
+            level: p.level.number(),
+            score: p.grid.cur_score(),
+            finesse_faults: p.stats.finesse_faults,
+            effects: p.effects.iter().map(|e| e.name().to_string()).collect(),
+        }
+    }
+
+    /// This board with the cells under blind replaced by `'?'` and the
+    /// falling block's hidden cells left out.
+    pub fn masked(&self) -> Self {
+        let mut view = self.clone();
+        view.rows = self.rows.iter().enumerate()
+            .map(|(r, row)| row.chars().enumerate().map(|(c, ch)| if in_blind(r, c) { '?' } else { ch }).collect())
+            .collect();
+        view.cells.retain(|&(r, c)| !in_blind(r as usize, c as usize));
+        view
+    }
+
+    pub fn matrix(&self) -> Vec<Vec<char>> {
+        self.rows.iter().map(|r| r.chars().collect()).collect()
+    }
+}
+
+/// Authoritative game state as sent to network clients and other frontends.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GameSnapshot {
+    pub players: [BoardView; 2],
+    pub hi_score: i32,
+    pub current_player: i32,
+    pub running: bool,
+    pub loser: Option<i32>,
+    /// Player who still has to choose a special action, if any.
+    pub awaiting_special: Option<i32>,
+}
+
+impl GameSnapshot {
+    pub fn board(&self, player: i32) -> &BoardView {
+        &self.players[if player == 1 { 0 } else { 1 }]
+    }
+
+    /// The state as `seat` may see it: a player under blind gets their own
+    /// board masked. The opponent and spectators see everything.
+    pub fn for_seat(&self, seat: i32) -> Self {
+        let mut snap = self.clone();
+        for (i, view) in snap.players.iter_mut().enumerate() {
+            if view.blind && seat == i as i32 + 1 {
+                *view = view.masked();
+            }
+        }
+        snap
+    }
+}
+
+impl Game {
+    pub fn snapshot(&self) -> GameSnapshot {
+        GameSnapshot {
+            players: [BoardView::from_player(&self.p1), BoardView::from_player(&self.p2)],
+            hi_score: self.system_hi,
+            current_player: self.current_player,
+            running: self.running,
+            loser: self.loser,
+            awaiting_special: None,
+        }
+    }
+}
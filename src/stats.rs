@@ -0,0 +1,163 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::block::BlockKind;
+
+const PIECE_KINDS: [char; 7] = ['T', 'S', 'Z', 'I', 'J', 'L', 'O'];
+
+/// Cumulative per-player counters for one game. Reset on `restart`.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PlayerStats {
+    pub pieces_placed: u32,
+    pub pieces_by_kind: BTreeMap<char, u32>,
+    pub moves: u32,
+    pub lines_cleared: u32,
+    pub singles: u32,
+    pub doubles: u32,
+    pub triples: u32,
+    pub tetrises: u32,
+    pub stars_dropped: u32,
+    pub specials_sent: u32,
+    pub specials_received: u32,
+    pub max_stack_height: i32,
+    pub holes_created: i32,
+    pub finesse_faults: u32,
+
+    // hole count left on the board after the previous landing
+    #[serde(skip)]
+    last_holes: i32,
+}
+
+impl PlayerStats {
+    pub fn new() -> Self {
+        let mut pieces_by_kind = BTreeMap::new();
+        for k in PIECE_KINDS {
+            pieces_by_kind.insert(k, 0);
+        }
+        Self { pieces_by_kind, ..Default::default() }
+    }
+
+    pub fn record_move(&mut self) {
+        self.moves += 1;
+    }
+
+    /// Called once the piece is locked into the grid, before rows are cleared.
+    pub fn record_lock(&mut self, kind: BlockKind, stack_height: i32, holes: i32) {
+        self.pieces_placed += 1;
+        *self.pieces_by_kind.entry(kind.to_char()).or_insert(0) += 1;
+        self.max_stack_height = self.max_stack_height.max(stack_height);
+        self.holes_created += (holes - self.last_holes).max(0);
+    }
+
+    pub fn record_clear(&mut self, cleared: i32) {
+        if cleared <= 0 { return; }
+        self.lines_cleared += cleared as u32;
+        match cleared {
+            1 => self.singles += 1,
+            2 => self.doubles += 1,
+            3 => self.triples += 1,
+            _ => self.tetrises += 1,
+        }
+    }
+
+    /// Called after all clears for a landing (including star drops) are done.
+    pub fn record_settled(&mut self, holes: i32) {
+        self.last_holes = holes;
+    }
+
+    pub fn pieces_per_move(&self) -> f64 {
+        if self.moves == 0 { return 0.0; }
+        self.pieces_placed as f64 / self.moves as f64
+    }
+
+    pub fn lines_per_piece(&self) -> f64 {
+        if self.pieces_placed == 0 { return 0.0; }
+        self.lines_cleared as f64 / self.pieces_placed as f64
+    }
+
+    pub fn summary(&self) -> String {
+        let by_kind: Vec<String> = self.pieces_by_kind.iter()
+            .map(|(k, n)| format!("{}:{}", k, n))
+            .collect();
+        let mut out = String::new();
+        out.push_str(&format!("  Pieces placed:     {} ({})\n", self.pieces_placed, by_kind.join(" ")));
+        out.push_str(&format!("  Moves:             {}\n", self.moves));
+        out.push_str(&format!("  Lines cleared:     {} (singles {}, doubles {}, triples {}, tetrises {})\n",
+            self.lines_cleared, self.singles, self.doubles, self.triples, self.tetrises));
+        out.push_str(&format!("  Star blocks:       {}\n", self.stars_dropped));
+        out.push_str(&format!("  Specials sent:     {}\n", self.specials_sent));
+        out.push_str(&format!("  Specials received: {}\n", self.specials_received));
+        out.push_str(&format!("  Max stack height:  {}\n", self.max_stack_height));
+        out.push_str(&format!("  Holes created:     {}\n", self.holes_created));
+        out.push_str(&format!("  Finesse faults:    {}\n", self.finesse_faults));
+        out.push_str(&format!("  Pieces per move:   {:.3}\n", self.pieces_per_move()));
+        out.push_str(&format!("  Lines per piece:   {:.3}\n", self.lines_per_piece()));
+        out
+    }
+}
+
+#[derive(Serialize)]
+struct StatsRecord<'a> {
+    player: i32,
+    score: i32,
+    #[serde(flatten)]
+    stats: &'a PlayerStats,
+    pieces_per_move: f64,
+    lines_per_piece: f64,
+}
+
+/// End-of-game summary for both players, as printed by the binaries.
+pub fn format_report(players: &[(&PlayerStats, i32)]) -> String {
+    let mut out = String::from("\n===== Game summary =====\n");
+    for (i, (stats, score)) in players.iter().enumerate() {
+        out.push_str(&format!("Player {} (score {}):\n", i + 1, score));
+        out.push_str(&stats.summary());
+    }
+    out
+}
+
+pub fn to_json(players: &[(&PlayerStats, i32)]) -> String {
+    let records: Vec<StatsRecord> = players.iter().enumerate()
+        .map(|(i, (stats, score))| StatsRecord {
+            player: i as i32 + 1,
+            score: *score,
+            stats,
+            pieces_per_move: stats.pieces_per_move(),
+            lines_per_piece: stats.lines_per_piece(),
+        })
+        .collect();
+    serde_json::to_string_pretty(&records).unwrap_or_else(|_| "[]".to_string())
+}
+
+pub fn to_csv(players: &[(&PlayerStats, i32)]) -> String {
+    let mut out = String::from("player,score,pieces_placed");
+    for k in PIECE_KINDS {
+        out.push_str(&format!(",pieces_{}", k));
+    }
+    out.push_str(",moves,lines_cleared,singles,doubles,triples,tetrises,stars_dropped,\
+specials_sent,specials_received,max_stack_height,holes_created,finesse_faults,pieces_per_move,lines_per_piece\n");
+
+    for (i, (s, score)) in players.iter().enumerate() {
+        out.push_str(&format!("{},{},{}", i + 1, score, s.pieces_placed));
+        for k in PIECE_KINDS {
+            out.push_str(&format!(",{}", s.pieces_by_kind.get(&k).copied().unwrap_or(0)));
+        }
+        out.push_str(&format!(",{},{},{},{},{},{},{},{},{},{},{},{},{:.4},{:.4}\n",
+            s.moves, s.lines_cleared, s.singles, s.doubles, s.triples, s.tetrises, s.stars_dropped,
+            s.specials_sent, s.specials_received, s.max_stack_height, s.holes_created, s.finesse_faults,
+            s.pieces_per_move(), s.lines_per_piece()));
+    }
+    out
+}
+
+/// Writes the JSON and/or CSV exports requested on the command line.
+pub fn export(players: &[(&PlayerStats, i32)], json_file: Option<&str>, csv_file: Option<&str>) -> Result<(), String> {
+    if let Some(f) = json_file {
+        std::fs::write(f, to_json(players)).map_err(|e| format!("Cannot write stats file {}: {}", f, e))?;
+    }
+    if let Some(f) = csv_file {
+        std::fs::write(f, to_csv(players)).map_err(|e| format!("Cannot write stats file {}: {}", f, e))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,448 @@
+//! Round-robin and single-elimination tournaments between roster entries.
+//!
+//! A roster file lists one entrant per line, a name and a seat:
+//!
+//! ```text
+//! # name   seat
+//! alice    human
+//! medium   bot:medium
+//! pybot    exec:python3 mybot.py
+//! ```
+//!
+//! `human` entrants play through the network server (`net`), connecting with
+//! `-name <name>`; any other seat is a controller spec (`controller::ControllerSpec`).
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::controller::{Controller, ControllerSpec};
+use crate::game::Game;
+use crate::level::LevelSet;
+use crate::net::Server;
+use crate::sim::{self, GameResult, SimOptions};
+
+#[derive(Debug, Clone)]
+pub enum Seat {
+    Human,
+    Computer(ControllerSpec),
+}
+
+#[derive(Debug, Clone)]
+pub struct Entrant {
+    pub name: String,
+    pub seat: Seat,
+}
+
+pub fn parse_roster(text: &str) -> Result<Vec<Entrant>, String> {
+    let mut entrants: Vec<Entrant> = Vec::new();
+    for (n, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') { continue; }
+        let (name, seat) = line.split_once(char::is_whitespace)
+            .ok_or_else(|| format!("roster line {}: expected '<name> <seat>'", n + 1))?;
+        let seat = match seat.trim() {
+            "human" => Seat::Human,
+            spec => Seat::Computer(ControllerSpec::parse(spec).map_err(|e| format!("roster line {}: {}", n + 1, e))?),
+        };
+        if entrants.iter().any(|e| e.name == name) {
+            return Err(format!("roster line {}: duplicate name '{}'", n + 1, name));
+        }
+        entrants.push(Entrant { name: name.to_string(), seat });
+    }
+    if entrants.len() < 2 {
+        return Err("a tournament needs at least two entrants".to_string());
+    }
+    Ok(entrants)
+}
+
+pub fn load_roster(path: &str) -> Result<Vec<Entrant>, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("Cannot read roster {}: {}", path, e))?;
+    parse_roster(&text)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    RoundRobin,
+    Elimination,
+}
+
+impl Format {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "roundrobin" | "rr" => Some(Format::RoundRobin),
+            "elimination" | "knockout" | "ko" => Some(Format::Elimination),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MatchOptions {
+    pub base_seed: u64,
+    pub level: i32,
+    pub script1: String,
+    pub script2: String,
+    pub max_pieces: u32,
+    /// Games per match; sides alternate between games.
+    pub games_per_match: u32,
+    pub bot_timeout: Duration,
+}
+
+impl MatchOptions {
+    /// Every match of a round plays the same seeds, so all pairings see the same pieces.
+    pub fn seed(&self, round: usize, game: u32) -> u64 {
+        self.base_seed.wrapping_add(round as u64 * 1000).wrapping_add(game as u64)
+    }
+}
+
+/// One game of a match, from the point of view of the match's `players`.
+#[derive(Debug, Clone)]
+pub struct GameRecord {
+    pub seed: u64,
+    /// Index into the match's `players` of the winner, if any.
+    pub winner: Option<usize>,
+    pub scores: [i32; 2],
+    pub lines: [u32; 2],
+    pub pieces: u32,
+}
+
+#[derive(Debug, Clone)]
+pub struct MatchResult {
+    pub round: usize,
+    /// Entrant indices.
+    pub players: [usize; 2],
+    pub games: Vec<GameRecord>,
+}
+
+impl MatchResult {
+    pub fn wins(&self) -> [u32; 2] {
+        let mut w = [0, 0];
+        for g in &self.games {
+            if let Some(i) = g.winner { w[i] += 1; }
+        }
+        w
+    }
+
+    pub fn total_scores(&self) -> [i32; 2] {
+        self.games.iter().fold([0, 0], |acc, g| [acc[0] + g.scores[0], acc[1] + g.scores[1]])
+    }
+
+    /// Entrant index of the match winner by games won; `None` for a draw.
+    pub fn winner(&self) -> Option<usize> {
+        let w = self.wins();
+        if w[0] > w[1] { Some(self.players[0]) } else if w[1] > w[0] { Some(self.players[1]) } else { None }
+    }
+
+    /// 1, ½ or 0 for the first player, as used by Elo.
+    pub fn score_first(&self) -> f64 {
+        match self.winner() {
+            Some(w) if w == self.players[0] => 1.0,
+            Some(_) => 0.0,
+            None => 0.5,
+        }
+    }
+}
+
+/// Plays one game. Computer-only games run headless; anything with a human
+/// seat is hosted on `server` and waits for the players to connect.
+fn play_game(
+    entrants: &[Entrant],
+    sides: [usize; 2],
+    seed: u64,
+    opts: &MatchOptions,
+    server: Option<&mut Server>,
+) -> Result<GameResult, String> {
+    let (a, b) = (&entrants[sides[0]], &entrants[sides[1]]);
+    let build = |e: &Entrant, offset: u64| -> Result<Option<Box<dyn Controller>>, String> {
+        match &e.seat {
+            Seat::Human => Ok(None),
+            Seat::Computer(spec) => spec.build(seed.wrapping_mul(2) + offset, opts.bot_timeout).map(Some),
+        }
+    };
+    let mut controllers = [build(a, 0)?, build(b, 1)?];
+
+    if let [Some(c1), Some(c2)] = &mut controllers {
+        let sim_opts = SimOptions {
+            seed,
+            level: opts.level,
+            script1: opts.script1.clone(),
+            script2: opts.script2.clone(),
+            max_pieces: opts.max_pieces,
+            levels: LevelSet::builtin(),
+        };
+        return sim::play_game(&sim_opts, c1.as_mut(), c2.as_mut());
+    }
+
+    let server = server.ok_or("human entrants need a server (-serve)")?;
+    let names = [a, b].map(|e| matches!(e.seat, Seat::Human).then(|| e.name.clone()));
+    server.reserve_seats(names);
+    let addr = server.local_addr().map(|a| a.to_string()).unwrap_or_default();
+    println!("{} vs {}: connect to {} with -name <your name>", a.name, b.name, addr);
+
+    let mut game = Game::new(Some(seed), opts.level, opts.script1.clone(), opts.script2.clone())?;
+    game.quiet = true;
+    game.track_finesse = false;
+    server.run(&mut game, &mut controllers).map_err(|e| e.to_string())?;
+
+    Ok(GameResult {
+        seed,
+        level: opts.level,
+        loser: game.loser,
+        pieces: game.p1.stats.pieces_placed + game.p2.stats.pieces_placed,
+        scores: [game.p1.grid.cur_score(), game.p2.grid.cur_score()],
+        stats: [game.p1.stats.clone(), game.p2.stats.clone()],
+    })
+}
+
+/// Plays a match of `opts.games_per_match` games, swapping who moves first each game.
+pub fn play_match(
+    entrants: &[Entrant],
+    players: [usize; 2],
+    round: usize,
+    opts: &MatchOptions,
+    mut server: Option<&mut Server>,
+) -> Result<MatchResult, String> {
+    let mut games = Vec::new();
+    for g in 0..opts.games_per_match.max(1) {
+        let seed = opts.seed(round, g);
+        let swapped = g % 2 == 1;
+        let sides = if swapped { [players[1], players[0]] } else { players };
+        let r = play_game(entrants, sides, seed, opts, server.as_deref_mut())?;
+
+        // back to the match's order
+        let side = |i: usize| if swapped { 1 - i } else { i };
+        games.push(GameRecord {
+            seed,
+            winner: r.winner().map(|w| side((w - 1) as usize)),
+            scores: [r.scores[side(0)], r.scores[side(1)]],
+            lines: [r.stats[side(0)].lines_cleared, r.stats[side(1)].lines_cleared],
+            pieces: r.pieces,
+        });
+    }
+    Ok(MatchResult { round, players, games })
+}
+
+/// Circle-method pairings: every entrant meets every other once.
+/// With an odd count one entrant sits out each round.
+pub fn round_robin(n: usize) -> Vec<Vec<[usize; 2]>> {
+    let mut ring: Vec<Option<usize>> = (0..n).map(Some).collect();
+    if n % 2 == 1 { ring.push(None); }
+    let m = ring.len();
+
+    let mut rounds = Vec::new();
+    for _ in 0..m - 1 {
+        let mut pairs = Vec::new();
+        for i in 0..m / 2 {
+            if let (Some(a), Some(b)) = (ring[i], ring[m - 1 - i]) {
+                pairs.push([a, b]);
+            }
+        }
+        rounds.push(pairs);
+        // keep the first slot fixed, rotate the rest
+        let last = ring.pop().unwrap();
+        ring.insert(1, last);
+    }
+    rounds
+}
+
+/// Standard bracket slots for `size` (a power of two): seed 1 meets the last seed,
+/// and seeds 1 and 2 can only meet in the final. Slots past the last entrant are byes.
+pub fn bracket_slots(size: usize) -> Vec<usize> {
+    let mut slots = vec![0];
+    while slots.len() < size {
+        let n = slots.len() * 2;
+        slots = slots.iter().flat_map(|&s| [s, n - 1 - s]).collect();
+    }
+    slots
+}
+
+/// Who goes through from an elimination match: the match winner; a drawn
+/// match goes to the higher total score, then the higher seed (lower `rank`,
+/// indexed by entrant).
+pub fn elimination_winner(result: &MatchResult, rank: &[usize]) -> usize {
+    let [a, b] = result.players;
+    result.winner().unwrap_or_else(|| {
+        let s = result.total_scores();
+        if s[1] > s[0] || (s[1] == s[0] && rank[b] < rank[a]) { b } else { a }
+    })
+}
+
+/// Elo ratings by entrant name, kept in a JSON file between tournaments.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Ratings {
+    pub ratings: BTreeMap<String, f64>,
+}
+
+impl Ratings {
+    pub const INITIAL: f64 = 1500.0;
+    pub const K: f64 = 32.0;
+
+    /// Loads `path`, starting empty if it does not exist yet.
+    pub fn load(path: &str) -> Result<Self, String> {
+        match std::fs::read_to_string(path) {
+            Ok(text) => serde_json::from_str(&text).map_err(|e| format!("Bad ratings file {}: {}", path, e)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(format!("Cannot read ratings {}: {}", path, e)),
+        }
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let text = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        std::fs::write(path, text + "\n").map_err(|e| format!("Cannot write ratings {}: {}", path, e))
+    }
+
+    pub fn get(&self, name: &str) -> f64 {
+        self.ratings.get(name).copied().unwrap_or(Self::INITIAL)
+    }
+
+    /// `score_a` is 1 for a win by `a`, ½ for a draw, 0 for a loss.
+    pub fn update(&mut self, a: &str, b: &str, score_a: f64) {
+        let (ra, rb) = (self.get(a), self.get(b));
+        let expected_a = 1.0 / (1.0 + 10f64.powf((rb - ra) / 400.0));
+        let delta = Self::K * (score_a - expected_a);
+        self.ratings.insert(a.to_string(), ra + delta);
+        self.ratings.insert(b.to_string(), rb - delta);
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Standing {
+    pub entrant: usize,
+    pub played: u32,
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+    pub score_for: i64,
+    pub score_against: i64,
+    pub lines: u32,
+}
+
+impl Standing {
+    /// Match points: 1 per win, ½ per draw.
+    pub fn points(&self) -> f64 {
+        self.wins as f64 + self.draws as f64 * 0.5
+    }
+}
+
+/// Table sorted by points, then score difference.
+pub fn standings(n: usize, results: &[MatchResult]) -> Vec<Standing> {
+    let mut table: Vec<Standing> = (0..n).map(|entrant| Standing { entrant, ..Default::default() }).collect();
+    for r in results {
+        let scores = r.total_scores();
+        let winner = r.winner();
+        for side in 0..2 {
+            let s = &mut table[r.players[side]];
+            s.played += 1;
+            match winner {
+                None => s.draws += 1,
+                Some(w) if w == r.players[side] => s.wins += 1,
+                Some(_) => s.losses += 1,
+            }
+            s.score_for += scores[side] as i64;
+            s.score_against += scores[1 - side] as i64;
+            s.lines += r.games.iter().map(|g| g.lines[side]).sum::<u32>();
+        }
+    }
+    table.sort_by(|a, b| {
+        b.points().total_cmp(&a.points())
+            .then((b.score_for - b.score_against).cmp(&(a.score_for - a.score_against)))
+    });
+    table
+}
+
+pub fn format_standings(entrants: &[Entrant], table: &[Standing], ratings: &Ratings) -> String {
+    let mut out = format!("{:<4}{:<16}{:>4}{:>4}{:>4}{:>4}{:>7}{:>9}{:>9}{:>7}{:>8}\n",
+        "#", "Name", "P", "W", "D", "L", "Pts", "For", "Against", "Lines", "Elo");
+    for (i, s) in table.iter().enumerate() {
+        let name = &entrants[s.entrant].name;
+        out.push_str(&format!("{:<4}{:<16}{:>4}{:>4}{:>4}{:>4}{:>7.1}{:>9}{:>9}{:>7}{:>8.0}\n",
+            i + 1, name, s.played, s.wins, s.draws, s.losses, s.points(),
+            s.score_for, s.score_against, s.lines, ratings.get(name)));
+    }
+    out
+}
+
+/// One CSV row per game.
+pub fn results_csv(entrants: &[Entrant], results: &[MatchResult]) -> String {
+    let mut out = String::from("round,player1,player2,game,seed,winner,score1,score2,lines1,lines2,pieces\n");
+    for r in results {
+        for (g, game) in r.games.iter().enumerate() {
+            out.push_str(&format!("{},{},{},{},{},{},{},{},{},{},{}\n",
+                r.round + 1, entrants[r.players[0]].name, entrants[r.players[1]].name, g + 1, game.seed,
+                game.winner.map(|w| entrants[r.players[w]].name.as_str()).unwrap_or(""),
+                game.scores[0], game.scores[1], game.lines[0], game.lines[1], game.pieces));
+        }
+    }
+    out
+}
+
+pub struct Tournament<'a> {
+    pub entrants: Vec<Entrant>,
+    pub format: Format,
+    pub opts: MatchOptions,
+    pub ratings: &'a mut Ratings,
+    pub results: Vec<MatchResult>,
+}
+
+impl Tournament<'_> {
+    /// Plays the whole event, updating ratings after every match.
+    /// `on_match` sees each result as it comes in. Returns the champion for
+    /// elimination events.
+    pub fn run(&mut self, mut server: Option<&mut Server>, on_match: &mut dyn FnMut(&Self, &MatchResult)) -> Result<Option<usize>, String> {
+        match self.format {
+            Format::RoundRobin => {
+                for (round, pairs) in round_robin(self.entrants.len()).into_iter().enumerate() {
+                    for players in pairs {
+                        self.play(players, round, server.as_deref_mut(), on_match)?;
+                    }
+                }
+                Ok(None)
+            }
+            Format::Elimination => self.run_elimination(server, on_match).map(Some),
+        }
+    }
+
+    fn play(&mut self, players: [usize; 2], round: usize, server: Option<&mut Server>, on_match: &mut dyn FnMut(&Self, &MatchResult)) -> Result<MatchResult, String> {
+        let result = play_match(&self.entrants, players, round, &self.opts, server)?;
+        let names = players.map(|p| self.entrants[p].name.clone());
+        self.ratings.update(&names[0], &names[1], result.score_first());
+        self.results.push(result.clone());
+        on_match(self, &result);
+        Ok(result)
+    }
+
+    /// Seeds by current rating (roster order breaks ties); byes go to the top seeds.
+    fn run_elimination(&mut self, mut server: Option<&mut Server>, on_match: &mut dyn FnMut(&Self, &MatchResult)) -> Result<usize, String> {
+        let mut seeds: Vec<usize> = (0..self.entrants.len()).collect();
+        seeds.sort_by(|&a, &b| self.ratings.get(&self.entrants[b].name).total_cmp(&self.ratings.get(&self.entrants[a].name)));
+
+        let mut rank = vec![0; seeds.len()];
+        for (r, &e) in seeds.iter().enumerate() {
+            rank[e] = r;
+        }
+
+        let size = seeds.len().next_power_of_two();
+        let mut alive: Vec<Option<usize>> = bracket_slots(size).into_iter().map(|s| seeds.get(s).copied()).collect();
+
+        let mut round = 0;
+        while alive.len() > 1 {
+            let mut next = Vec::new();
+            for pair in alive.chunks(2) {
+                let winner = match (pair[0], pair[1]) {
+                    (Some(a), Some(b)) => {
+                        let r = self.play([a, b], round, server.as_deref_mut(), on_match)?;
+                        Some(elimination_winner(&r, &rank))
+                    }
+                    (Some(a), None) | (None, Some(a)) => Some(a),
+                    (None, None) => None,
+                };
+                next.push(winner);
+            }
+            alive = next;
+            round += 1;
+        }
+        alive[0].ok_or_else(|| "empty bracket".to_string())
+    }
+}
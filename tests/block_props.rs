@@ -0,0 +1,152 @@
+//! Property tests for `Block` movement and rotation on random stacks.
+
+use std::collections::BTreeSet;
+
+use proptest::prelude::*;
+use tetris::block::{Block, BlockKind};
+use tetris::grid::{Grid, COLS, PLAY_BOTTOM, PLAY_TOP};
+
+const BLOCK_ID: i32 = 1;
+/// Stacks stay below the spawn rows so every block can enter the board.
+const STACK_TOP: usize = 10;
+
+#[derive(Debug, Clone, Copy)]
+enum Op {
+    Down,
+    Left,
+    Right,
+    Cw,
+    Ccw,
+    Drop,
+}
+
+fn kind() -> impl Strategy<Value = BlockKind> {
+    prop::sample::select("TSZIJLO*".chars().filter_map(BlockKind::from_char).collect::<Vec<_>>())
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    prop::sample::select(vec![Op::Down, Op::Left, Op::Right, Op::Cw, Op::Ccw, Op::Drop])
+}
+
+/// Settled cells as `(row, col, char)`; each gets its own block id.
+fn stack() -> impl Strategy<Value = Vec<(usize, usize, char)>> {
+    prop::collection::vec((STACK_TOP..=PLAY_BOTTOM, 0..COLS, prop::sample::select(vec!['T', 'S', 'Z', 'I', 'J', 'L', 'O', '*'])), 0..60)
+}
+
+/// A grid holding `cells`, and the settled cells with the ids they were given.
+fn grid_with(cells: &[(usize, usize, char)]) -> (Grid, Vec<(usize, usize, char, i32)>) {
+    let mut g = Grid::new();
+    let mut settled = Vec::new();
+    let mut seen = BTreeSet::new();
+    for (i, &(r, c, ch)) in cells.iter().enumerate() {
+        if seen.insert((r, c)) {
+            let id = 100 + i as i32;
+            g.set_cell(r, c, ch, id);
+            settled.push((r, c, ch, id));
+        }
+    }
+    (g, settled)
+}
+
+fn placement(b: &Block) -> BTreeSet<(i32, i32, char)> {
+    b.cells.iter().map(|c| (c.r, c.c, c.ch)).collect()
+}
+
+fn apply(op: Op, b: &mut Block, g: &mut Grid) {
+    match op {
+        Op::Down => { b.move_down(g); }
+        Op::Left => { b.move_left(g); }
+        Op::Right => { b.move_right(g); }
+        Op::Cw => { b.rotate_cw(g); }
+        Op::Ccw => { b.rotate_ccw(g); }
+        Op::Drop => b.drop(g),
+    }
+}
+
+/// The block is drawn where it says it is, the stack is untouched, and
+/// `matrix` and `block_id` agree on which play-area cells are empty.
+fn check_invariants(b: &Block, g: &Grid, settled: &[(usize, usize, char, i32)], cell_count: usize) -> Result<(), TestCaseError> {
+    prop_assert_eq!(b.cells.len(), cell_count);
+    for cell in &b.cells {
+        prop_assert!((PLAY_TOP as i32..=PLAY_BOTTOM as i32).contains(&cell.r) && (0..COLS as i32).contains(&cell.c), "cell out of bounds: {:?}", cell);
+        let (r, c) = (cell.r as usize, cell.c as usize);
+        prop_assert_eq!(g.get(r, c), cell.ch);
+        prop_assert_eq!(g.get_block_id(r, c), BLOCK_ID);
+    }
+    for &(r, c, ch, id) in settled {
+        prop_assert_eq!(g.get(r, c), ch, "settled cell ({}, {}) overwritten", r, c);
+        prop_assert_eq!(g.get_block_id(r, c), id);
+    }
+    let own: BTreeSet<(usize, usize)> = b.cells.iter().map(|c| (c.r as usize, c.c as usize)).collect();
+    for r in PLAY_TOP..=PLAY_BOTTOM {
+        for c in 0..COLS {
+            let (ch, id) = (g.get(r, c), g.get_block_id(r, c));
+            prop_assert_eq!(ch == ' ', id == -1, "matrix {:?} but block id {} at ({}, {})", ch, id, r, c);
+            if id == BLOCK_ID {
+                prop_assert!(own.contains(&(r, c)), "stale cell of the block at ({}, {})", r, c);
+            }
+        }
+    }
+    Ok(())
+}
+
+proptest! {
+    #[test]
+    fn moves_preserve_grid_invariants(kind in kind(), cells in stack(), ops in prop::collection::vec(op(), 0..40)) {
+        let (mut g, settled) = grid_with(&cells);
+        let mut b = Block::new(kind, BLOCK_ID);
+        prop_assume!(b.can_spawn(&g));
+        b.write_to_grid(&mut g);
+        let count = b.cells.len();
+        check_invariants(&b, &g, &settled, count)?;
+        for op in ops {
+            apply(op, &mut b, &mut g);
+            check_invariants(&b, &g, &settled, count)?;
+        }
+    }
+
+    #[test]
+    fn blocked_moves_change_nothing(kind in kind(), cells in stack(), ops in prop::collection::vec(op(), 0..40)) {
+        let (mut g, _) = grid_with(&cells);
+        let mut b = Block::new(kind, BLOCK_ID);
+        prop_assume!(b.can_spawn(&g));
+        b.write_to_grid(&mut g);
+        for op in ops {
+            let (before, matrix) = (placement(&b), g.matrix().clone());
+            let moved = match op {
+                Op::Down => b.move_down(&mut g),
+                Op::Left => b.move_left(&mut g),
+                Op::Right => b.move_right(&mut g),
+                Op::Cw => b.rotate_cw(&mut g),
+                Op::Ccw => b.rotate_ccw(&mut g),
+                Op::Drop => { b.drop(&mut g); true }
+            };
+            if !moved {
+                prop_assert_eq!(placement(&b), before);
+                prop_assert!(g.matrix() == &matrix, "a blocked {:?} changed the grid", op);
+            }
+        }
+    }
+
+    #[test]
+    fn cw_then_ccw_restores_placement(kind in kind(), cells in stack(), ops in prop::collection::vec(op(), 0..20)) {
+        let (mut g, settled) = grid_with(&cells);
+        let mut b = Block::new(kind, BLOCK_ID);
+        prop_assume!(b.can_spawn(&g));
+        b.write_to_grid(&mut g);
+        for op in ops {
+            apply(op, &mut b, &mut g);
+        }
+        let before = placement(&b);
+        // the placement being rotated back to is the block's own, so it is never obstructed
+        if b.rotate_cw(&mut g) {
+            prop_assert!(b.rotate_ccw(&mut g), "ccw back to {:?} failed", before);
+            prop_assert_eq!(&placement(&b), &before);
+            check_invariants(&b, &g, &settled, b.cells.len())?;
+        }
+        if b.rotate_ccw(&mut g) {
+            prop_assert!(b.rotate_cw(&mut g), "cw back to {:?} failed", before);
+            prop_assert_eq!(placement(&b), before);
+        }
+    }
+}
@@ -0,0 +1,93 @@
+/* Drives a whole game through the C API; run by tests/ffi.rs. */
+#include <stdio.h>
+#include <string.h>
+
+#include "tetris.h"
+
+#define CHECK(cond)                                                   \
+    do {                                                              \
+        if (!(cond)) {                                                \
+            fprintf(stderr, "%s:%d: check failed: %s\n", __FILE__,    \
+                    __LINE__, #cond);                                 \
+            return 1;                                                 \
+        }                                                             \
+    } while (0)
+
+static int filled(const TetrisGame *g, int player) {
+    int n = 0;
+    for (int r = 0; r < tetris_rows(); r++)
+        for (int c = 0; c < tetris_cols(); c++)
+            if (tetris_cell(g, player, r, c) != ' ') n++;
+    return n;
+}
+
+int main(void) {
+    TetrisGame *g = tetris_new("-seed 3");
+    CHECK(g != NULL);
+    CHECK(tetris_running(g));
+    CHECK(tetris_current_player(g) == 1);
+    CHECK(tetris_level(g, 1) == 0 && tetris_score(g, 1) == 0);
+    CHECK(tetris_cell(g, 1, tetris_rows(), 0) == 0);
+
+    /* a falling block is drawn in the play area */
+    CHECK(filled(g, 1) == 4);
+    CHECK(tetris_command(g, "2left drop") == 0);
+    CHECK(tetris_current_player(g) == 2);
+    CHECK(filled(g, 1) == 8);
+
+    CHECK(tetris_command(g, "levelup") == 0);
+    CHECK(tetris_level(g, 2) == 1);
+    CHECK(tetris_command(g, "3leveld") == 0);
+    CHECK(tetris_level(g, 2) == 0);
+
+    /* huge multipliers saturate at the defined levels instead of overflowing */
+    CHECK(tetris_command(g, "levelup 2147483647levelup") == 0);
+    CHECK(tetris_level(g, 2) == 4);
+    CHECK(tetris_command(g, "2147483647leveldown") == 0);
+    CHECK(tetris_level(g, 2) == 0);
+
+    /* a token that is only a multiplier is an error, not a panic */
+    CHECK(tetris_command(g, "42") == -1);
+    CHECK(strstr(tetris_last_error(), "42") != NULL);
+
+    CHECK(tetris_command(g, "jump") == -1);
+    CHECK(tetris_last_error() != NULL);
+    CHECK(strstr(tetris_last_error(), "jump") != NULL);
+
+    /* aliases and macros persist between calls */
+    CHECK(tetris_command(g, "rename slam drop") == 0);
+    CHECK(tetris_command(g, "macro place left slam") == 0);
+    CHECK(tetris_command(g, "place") == 0);
+    CHECK(tetris_current_player(g) == 1);
+
+    char *json = tetris_state_json(g);
+    CHECK(json != NULL);
+    CHECK(strstr(json, "\"running\":true") != NULL);
+    tetris_string_free(json);
+
+    /* hard-drop until someone tops out, answering every prompt */
+    int turns = 0;
+    while (tetris_running(g) && turns++ < 1000) {
+        int rc = tetris_awaiting_special(g) ? tetris_command(g, "force Z") : tetris_command(g, "drop");
+        CHECK(rc == 0);
+    }
+    CHECK(!tetris_running(g));
+    CHECK(tetris_loser(g) == 1 || tetris_loser(g) == 2);
+    CHECK(tetris_command(g, "drop") == -1);
+
+    char *events = tetris_events_json(g);
+    CHECK(events != NULL);
+    CHECK(strstr(events, "\"type\":\"game_over\"") != NULL);
+    tetris_string_free(events);
+
+    CHECK(tetris_command(g, "restart") == 0);
+    CHECK(tetris_running(g) && tetris_loser(g) == 0);
+
+    tetris_free(g);
+    tetris_free(NULL);
+    CHECK(tetris_new("-startlevel 0 -scriptfile1 missing.txt") == NULL);
+    CHECK(tetris_last_error() != NULL);
+
+    puts("ok");
+    return 0;
+}
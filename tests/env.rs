@@ -0,0 +1,31 @@
+use tetris::env::{Action, Env, EnvConfig};
+use tetris::player::Move;
+
+#[test]
+fn step_before_reset_is_an_error() {
+    let mut env = Env::new(EnvConfig::default());
+    assert!(env.step(Action::Move(Move::Drop)).is_err());
+    assert!(env.game().is_none());
+
+    env.reset(7).unwrap();
+    let (_, _, done, info) = env.step(Action::Move(Move::Drop)).unwrap();
+    assert!(info.landed);
+    assert!(!done);
+}
+
+#[test]
+fn a_finished_episode_keeps_returning_done() {
+    let mut env = Env::new(EnvConfig { max_pieces: 2, ..EnvConfig::default() });
+    env.reset(7).unwrap();
+    let mut steps = 0;
+    loop {
+        let action = env.legal_actions()[5];
+        let (_, _, done, _) = env.step(action).unwrap();
+        steps += 1;
+        if done { break; }
+        assert!(steps < 100, "episode did not end");
+    }
+    let (_, reward, done, _) = env.step(Action::Move(Move::Drop)).unwrap();
+    assert_eq!(reward, 0.0);
+    assert!(done);
+}
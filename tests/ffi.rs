@@ -0,0 +1,34 @@
+use std::path::PathBuf;
+use std::process::Command;
+
+/// Compiles `tests/c/ffi_smoke.c` against `include/tetris.h` and the cdylib,
+/// then runs it from the crate root (for the default sequence files).
+#[test]
+fn c_program_drives_a_game() {
+    let root = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    // the cdylib is built next to this test binary, in target/<profile>/deps
+    let lib_dir = std::env::current_exe().unwrap().parent().unwrap().to_path_buf();
+    let exe = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("ffi_smoke");
+
+    let cc = std::env::var("CC").unwrap_or_else(|_| "cc".to_string());
+    let status = Command::new(&cc)
+        .arg("-std=c99")
+        .arg("-Wall")
+        .arg("-Werror")
+        .arg(root.join("tests/c/ffi_smoke.c"))
+        .arg("-I")
+        .arg(root.join("include"))
+        .arg("-L")
+        .arg(&lib_dir)
+        .arg(format!("-Wl,-rpath,{}", lib_dir.display()))
+        .arg("-ltetris")
+        .arg("-o")
+        .arg(&exe)
+        .status()
+        .unwrap_or_else(|e| panic!("cannot run {}: {}", cc, e));
+    assert!(status.success(), "compiling the C test failed");
+
+    let out = Command::new(&exe).current_dir(&root).output().unwrap();
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    assert_eq!(String::from_utf8_lossy(&out.stdout).trim(), "ok");
+}
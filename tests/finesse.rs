@@ -0,0 +1,78 @@
+use tetris::block::{Block, BlockKind};
+use tetris::finesse::{analyze, PieceFinesse, PieceTrace};
+use tetris::grid::{Grid, COLS};
+use tetris::player::{step_block, Move, MoveOutcome};
+
+use Move::*;
+
+/// Plays `inputs` on a fresh `kind` on an empty board until it lands, with
+/// the trace recording `trace_heavy` as the flags seen at the first input.
+fn play(kind: BlockKind, inputs: &[Move], heavy: (i32, i32), trace_heavy: (i32, i32)) -> Option<PieceFinesse> {
+    let mut grid = Grid::new();
+    let mut block = Block::new(kind, 1);
+    block.write_to_grid(&mut grid);
+    let mut trace = PieceTrace::new(block.clone(), trace_heavy.0, trace_heavy.1);
+    for &mv in inputs {
+        trace.inputs.push(mv);
+        if step_block(&mut grid, &mut block, mv, heavy.0, heavy.1) == MoveOutcome::Landed {
+            break;
+        }
+    }
+    analyze(&grid, &block, &trace, 1)
+}
+
+#[test]
+fn a_shortest_path_has_no_faults() {
+    let f = play(BlockKind::O, &[Right, Right, Right, Drop], (0, 0), (0, 0)).unwrap();
+    assert_eq!(f.faults, 0);
+    assert_eq!(f.optimal, [Right, Right, Right, Drop]);
+    assert_eq!((f.piece, f.kind), (1, BlockKind::O));
+
+    let f = play(BlockKind::O, &[Drop], (0, 0), (0, 0)).unwrap();
+    assert_eq!(f.faults, 0);
+}
+
+#[test]
+fn wasted_and_blocked_inputs_are_faults() {
+    // a detour: two inputs too many
+    let f = play(BlockKind::O, &[Right, Right, Left, Right, Drop], (0, 0), (0, 0)).unwrap();
+    assert_eq!(f.faults, 2);
+    assert_eq!(f.optimal, [Right, Right, Drop]);
+    assert_eq!(f.inputs, [Right, Right, Left, Right, Drop]);
+
+    // pushing into the wall changes nothing but still costs an input
+    let f = play(BlockKind::O, &[Left, Left, Drop], (0, 0), (0, 0)).unwrap();
+    assert_eq!(f.faults, 2);
+    assert_eq!(f.optimal, [Drop]);
+
+    // spinning a full turn lands where no rotation would have
+    let f = play(BlockKind::T, &[Cw, Cw, Cw, Cw, Drop], (0, 0), (0, 0)).unwrap();
+    assert_eq!(f.faults, 4);
+}
+
+#[test]
+fn an_unreachable_lock_position_has_no_verdict() {
+    // played without heavy pulls to the far wall, but the trace says every
+    // shift pulled the block down two rows, so it could never have got there
+    let far = vec![Right; COLS];
+    let mut inputs = far.clone();
+    inputs.push(Drop);
+    assert!(play(BlockKind::O, &inputs, (0, 0), (0, 0)).is_some());
+    assert!(play(BlockKind::O, &inputs, (0, 0), (0, 2)).is_none());
+}
+
+#[test]
+fn heavy_flags_from_the_first_input_are_used() {
+    // with heavy pulls the block lands on a shift, so no drop is needed
+    let inputs = vec![Right; COLS];
+    let f = play(BlockKind::O, &inputs, (0, 2), (0, 2)).unwrap();
+    assert_eq!(f.faults, 0);
+    assert_ne!(f.optimal.last(), Some(&Drop));
+    assert_eq!(f.optimal, f.inputs);
+
+    // heavy rows make each soft drop fall further, and the block locks on the floor
+    let f = play(BlockKind::O, &[Down; 20], (2, 0), (2, 0)).unwrap();
+    assert!(f.inputs.len() < 20);
+    assert_eq!(f.optimal, [Drop]);
+    assert_eq!(f.faults as usize, f.inputs.len() - 1);
+}
@@ -0,0 +1,102 @@
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// Set to `1` to write the current output to the `.expected` files instead of
+/// comparing against them.
+const BLESS_VAR: &str = "TETRIS_BLESS";
+
+/// Runs one `.cmd` script through the `text` binary. Lines starting with `#`
+/// are comments; `# args: ...` gives the command-line flags.
+fn run_script(root: &Path, script: &Path) -> String {
+    let source = fs::read_to_string(script).unwrap();
+    let mut args = Vec::new();
+    let mut input = String::new();
+    for line in source.lines() {
+        match line.trim_start().strip_prefix('#') {
+            Some(comment) => {
+                if let Some(flags) = comment.trim().strip_prefix("args:") {
+                    args.extend(flags.split_whitespace().map(str::to_string));
+                }
+            }
+            None => {
+                input.push_str(line);
+                input.push('\n');
+            }
+        }
+    }
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_text"))
+        .args(&args)
+        .current_dir(root)
+        // keep ~/.tetrisrc and the history file out of the test
+        .env("HOME", env!("CARGO_TARGET_TMPDIR"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("cannot run the text binary");
+    child.stdin.take().unwrap().write_all(input.as_bytes()).unwrap();
+    let out = child.wait_with_output().unwrap();
+
+    let mut text = String::from_utf8_lossy(&out.stdout).into_owned();
+    if !out.stderr.is_empty() {
+        text.push_str("--- stderr ---\n");
+        text.push_str(&String::from_utf8_lossy(&out.stderr));
+    }
+    if !out.status.success() {
+        text.push_str(&format!("--- exit status: {} ---\n", out.status.code().unwrap_or(-1)));
+    }
+    text
+}
+
+/// Where `actual` first differs from `expected`, for the failure message.
+fn first_difference(expected: &str, actual: &str) -> String {
+    let (mut e, mut a) = (expected.lines(), actual.lines());
+    for n in 1.. {
+        match (e.next(), a.next()) {
+            (Some(x), Some(y)) if x == y => continue,
+            (None, None) => break,
+            (x, y) => return format!("line {}:\n  expected: {:?}\n  actual:   {:?}", n, x, y),
+        }
+    }
+    "trailing whitespace differs".to_string()
+}
+
+#[test]
+fn golden_scripts() {
+    let root = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let bless = std::env::var(BLESS_VAR).is_ok_and(|v| v == "1");
+
+    let mut scripts: Vec<PathBuf> = fs::read_dir(root.join("tests/golden"))
+        .unwrap()
+        .map(|e| e.unwrap().path())
+        .filter(|p| p.extension().is_some_and(|x| x == "cmd"))
+        .collect();
+    scripts.sort();
+    assert!(!scripts.is_empty(), "no .cmd files in tests/golden");
+
+    let mut failures = Vec::new();
+    for script in &scripts {
+        let actual = run_script(&root, script);
+        let expected_path = script.with_extension("expected");
+        if bless {
+            fs::write(&expected_path, &actual).unwrap();
+            continue;
+        }
+        match fs::read_to_string(&expected_path) {
+            Ok(expected) if expected == actual => {}
+            Ok(expected) => failures.push(format!("{}: {}", script.display(), first_difference(&expected, &actual))),
+            Err(_) => failures.push(format!("{}: no .expected file", script.display())),
+        }
+    }
+    assert!(
+        failures.is_empty(),
+        "{} of {} golden scripts differ (rerun with {}=1 to accept the new output):\n{}",
+        failures.len(),
+        scripts.len(),
+        BLESS_VAR,
+        failures.join("\n")
+    );
+}
@@ -0,0 +1,17 @@
+# Aliases, abbreviations of aliases, parameterized macros and their errors.
+# args: -seed 1
+rename l left
+rename r right
+macro place $1r $2cw drop
+macro twice place 2 1 place 6 2
+expand twice
+describe twice
+r 2l
+place 4 1
+place 3
+twice
+macro loop loop
+unalias l
+l
+help pl
+quit
@@ -0,0 +1,3 @@
+# A spectator delay no Duration can hold is rejected at startup.
+# args: -delay 1e300
+drop
@@ -0,0 +1,3 @@
+# A level file naming a missing sequence file is rejected before the game starts.
+# args: -levelfile tests/golden/bad_sequence_level.json -startlevel 1
+drop
@@ -0,0 +1,12 @@
+# The same kind of session in -batch mode: one JSON line per command.
+# args: -seed 1 -batch -strict
+rename mv left
+3mv
+lfet
+cw drop
+macro p $1right drop
+p 4
+p
+}
+O 8right drop
+quit
@@ -0,0 +1,8 @@
+# A macro inside a repeat body that hits the expansion limit stops the loop;
+# later loops run on their own.
+# args: -seed 1 -batch
+macro m left right
+repeat 2 { 60000m cw }
+repeat 2 { cw }
+drop
+quit
@@ -0,0 +1,13 @@
+# Level 3: every move and rotation also pulls the block down a row.
+# args: -seed 7 -startlevel 3
+3right
+cw
+drop
+left2
+drop
+levelup
+ccw 2left
+drop
+leveldown leveldown
+drop
+quit
@@ -0,0 +1,2 @@
+# Each player reads their own input file; player 2's runs out first, which ends the game.
+# args: -batch -seed 7 -input1 tests/golden/input_p1.txt -input2 tests/golden/input_p2.txt
@@ -0,0 +1,4 @@
+# A level change that cannot load its sequence file is reported as an error.
+# args: -batch -seed 7 -startlevel 1 -scriptfile1 tests/golden/no_such_sequence.txt
+leveldown
+levelup
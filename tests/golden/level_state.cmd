@@ -0,0 +1,11 @@
+# Changing level keeps a norandom sequence on levels that allow it and
+# redraws the preview from the new level.
+# args: -seed 7 -startlevel 3
+norandom tests/golden/marathon_sequence.txt
+drop
+drop
+levelup
+drop
+drop
+leveldown leveldown
+quit
@@ -0,0 +1,15 @@
+# Levels from a level file: level 1 deals only I blocks and is twice as heavy,
+# level 2 deals O blocks and drops a star into column 0 every 2 pieces.
+# args: -seed 7 -startlevel 1 -levelfile tests/golden/custom_levels.json
+cw
+right
+random
+drop
+levelup
+drop
+levelup
+norandom tetris_sequence1.txt
+drop
+drop
+levelup
+quit
@@ -0,0 +1,8 @@
+# Marathon: one line goes up a level, and the new level deals the next block.
+# args: -seed 7 -marathon 1 -scriptfile1 tests/golden/marathon_sequence.txt
+drop
+drop
+4right drop
+drop
+8right drop
+quit
@@ -0,0 +1,8 @@
+# Prefix and suffix multipliers, abbreviations, and multipliers ignored by drop.
+# args: -seed 1
+3right
+ri2 2cw
+drop
+down3
+ccw2 le
+5drop
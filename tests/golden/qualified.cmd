@@ -0,0 +1,11 @@
+# Player-qualified commands: player 1's whole script first, then player 2's.
+# Lines for the player not on turn are held until their turn.
+# args: -seed 1 -batch
+p1:3right p1:drop
+p1:left drop
+p1:cw drop
+p2:5right drop
+p2:drop
+p2:2left cw drop
+p3:left
+quit
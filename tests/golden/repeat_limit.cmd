@@ -0,0 +1,8 @@
+# A repeat count above the loop limit is refused without running the body.
+# args: -seed 1 -batch
+repeat 2000000000 { left }
+set n 1000
+repeat $n+1 { left }
+repeat 2 { cw }
+drop
+quit
@@ -0,0 +1,42 @@
+# Player 1 clears two rows at a time and sends heavy, force and blind in turn;
+# player 2 plays under each effect.
+# args: -seed 1
+O drop
+drop
+O 2right drop
+5right drop
+O 4right drop
+8right drop
+O 6right drop
+drop
+O 8right drop
+5right drop
+I cw 10right drop
+heavy
+3right cw
+drop
+O drop
+8right drop
+O 2right drop
+drop
+O 4right drop
+5right drop
+O 6right drop
+8right drop
+O 8right drop
+force Z
+2right drop
+O drop
+drop
+O 2right drop
+5right drop
+O 4right drop
+8right drop
+O 6right drop
+drop
+O 8right drop
+5right drop
+I cw 10right drop
+blind
+8right drop
+quit
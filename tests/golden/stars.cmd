@@ -0,0 +1,15 @@
+# Level 4: after five pieces without a clear a one-cell star block drops.
+# args: -seed 3 -startlevel 4
+drop
+drop
+5right drop
+5right drop
+drop
+8right drop
+3right drop
+drop
+8right drop
+3right drop
+drop
+drop
+quit
@@ -0,0 +1,26 @@
+use tetris::block::BlockKind;
+use tetris::game::Game;
+use tetris::level::LevelRule;
+use tetris::player::Move;
+
+/// Drops one piece for player 1, spread across the board so nobody tops out.
+fn drop_piece(game: &mut Game, n: usize) {
+    game.apply_moves(1, [Move::Left, Move::Right][n % 2], (n % 5) as i32 * 2);
+    game.apply_move(1, Move::Drop);
+}
+
+#[test]
+fn an_added_rule_sees_each_spawned_piece_once() {
+    let mut game = Game::new(Some(3), 1, "tetris_sequence1.txt".into(), "tetris_sequence2.txt".into()).unwrap();
+    game.quiet = true;
+    game.p1.level.add_rule(LevelRule::ForcePiece { interval: 3, kind: BlockKind::Star, spawned: 0 });
+
+    let mut kinds = Vec::new();
+    for n in 0..9 {
+        drop_piece(&mut game, n);
+        assert!(game.running);
+        kinds.push(game.p1.cur.kind);
+    }
+    let forced: Vec<usize> = (0..kinds.len()).filter(|&i| kinds[i] == BlockKind::Star).collect();
+    assert_eq!(forced, [2, 5, 8]);
+}
@@ -0,0 +1,67 @@
+use std::collections::BTreeSet;
+
+use tetris::block::{Block, BlockKind};
+use tetris::grid::{Grid, COLS, PLAY_BOTTOM};
+use tetris::movegen::{placements_for, Placement};
+use tetris::player::Move;
+
+/// Placements of a freshly spawned `kind` on an empty board.
+fn placements(kind: BlockKind, heavy: i32, horizontal_extra: i32) -> Vec<Placement> {
+    let mut grid = Grid::new();
+    let block = Block::new(kind, 1);
+    block.write_to_grid(&mut grid);
+    placements_for(&grid, &block, heavy, horizontal_extra)
+}
+
+/// Leftmost column of a placement.
+fn left_col(p: &Placement) -> i32 {
+    p.cells().iter().map(|&(_, c)| c).min().unwrap()
+}
+
+#[test]
+fn every_column_is_reached_on_an_empty_board() {
+    let cols = COLS as i32;
+    // I: flat in every column but the last three, upright in every column
+    let i = placements(BlockKind::I, 0, 0);
+    assert_eq!(i.len(), (cols - 3 + cols) as usize);
+    // O: one per pair of adjacent columns
+    let o = placements(BlockKind::O, 0, 0);
+    assert_eq!(o.len(), (cols - 1) as usize);
+    assert_eq!(o.iter().map(left_col).collect::<BTreeSet<_>>(), (0..cols - 1).collect());
+
+    for p in i.iter().chain(&o) {
+        let cells = p.cells();
+        assert!(cells.iter().any(|&(r, _)| r == PLAY_BOTTOM as i32), "{:?} is not resting on the floor", cells);
+        assert_eq!(p.moves.last(), Some(&Move::Drop));
+    }
+    let unique: BTreeSet<_> = i.iter().map(|p| p.cells()).collect();
+    assert_eq!(unique.len(), i.len());
+}
+
+#[test]
+fn paths_are_as_short_as_possible() {
+    let o = placements(BlockKind::O, 0, 0);
+    let at = |col: i32| o.iter().find(|p| left_col(p) == col).unwrap();
+    assert_eq!(at(0).commands(), ["drop"]);
+    assert_eq!(at(3).commands(), ["right", "right", "right", "drop"]);
+    let edge = at(COLS as i32 - 2);
+    assert_eq!(edge.moves.len(), COLS - 1);
+    assert!(edge.moves[..COLS - 2].iter().all(|&m| m == Move::Right));
+
+    // upright I at the right wall: one rotation, then across
+    let i = placements(BlockKind::I, 0, 0);
+    let wall = i.iter()
+        .find(|p| p.cells().iter().all(|&(_, c)| c == COLS as i32 - 1))
+        .unwrap();
+    assert_eq!(wall.moves.iter().filter(|&&m| m == Move::Cw || m == Move::Ccw).count(), 1);
+    assert_eq!(wall.moves.len(), 1 + (COLS - 1) + 1);
+}
+
+#[test]
+fn heavy_pulls_leave_the_far_edge_out_of_reach() {
+    // each step right also pulls the block down, so it lands before the far wall
+    let heavy = placements(BlockKind::O, 0, 2);
+    let far = heavy.iter().map(left_col).max().unwrap();
+    assert!(far < COLS as i32 - 2, "O reached column {} with heavy pulls", far);
+    assert!(heavy.iter().any(|p| p.moves.last() != Some(&Move::Drop)));
+}
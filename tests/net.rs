@@ -0,0 +1,242 @@
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use tetris::effects::Effect;
+use tetris::events::GameEvent;
+use tetris::game::Game;
+use tetris::net::{Client, Server, ServerMsg, MAX_LINE, MAX_REPEAT};
+use tetris::render_text::in_blind;
+use tetris::player::Move;
+use tetris::snapshot::GameSnapshot;
+
+fn new_game() -> Game {
+    let mut game = Game::new(Some(3), 0, "tetris_sequence1.txt".to_string(), "tetris_sequence2.txt".to_string()).unwrap();
+    game.quiet = true;
+    game
+}
+
+/// Hosts one game on an ephemeral loopback port; returns the address and a
+/// handle yielding the final game.
+fn start_server() -> (String, thread::JoinHandle<Game>) {
+    let server = Server::bind("127.0.0.1:0").unwrap();
+    let addr = server.local_addr().unwrap().to_string();
+    let handle = thread::spawn(move || {
+        let mut game = new_game();
+        server.run(&mut game, &mut [None, None]).unwrap();
+        game
+    });
+    (addr, handle)
+}
+
+/// Hard-drops every piece and answers every special prompt with `heavy`;
+/// returns the last state seen and how many prompts it got.
+fn drop_everything(client: &mut Client) -> (GameSnapshot, u32) {
+    let me = client.seat;
+    let mut last = None;
+    let mut prompts = 0;
+    while let Some(msg) = client.recv() {
+        match msg {
+            ServerMsg::State { state } => {
+                let running = state.running;
+                if running && state.current_player == me && state.awaiting_special.is_none() {
+                    client.send_move(Move::Drop, 1).unwrap();
+                }
+                last = Some(state);
+                if !running { break; }
+            }
+            ServerMsg::ChooseSpecial { player } => {
+                assert_eq!(player, me);
+                prompts += 1;
+                client.send_special("heavy", None).unwrap();
+            }
+            ServerMsg::Error { message } => panic!("player {}: {}", me, message),
+            ServerMsg::Welcome { .. } | ServerMsg::Event { .. } => {}
+        }
+    }
+    (last.expect("no state received"), prompts)
+}
+
+#[test]
+fn two_clients_play_a_game_over_loopback() {
+    let (addr, server) = start_server();
+
+    let mut c1 = Client::connect(&addr, Some("one"), false).unwrap();
+    let mut c2 = Client::connect(&addr, Some("two"), false).unwrap();
+    assert_eq!((c1.seat, c2.seat), (1, 2));
+
+    // seats are full, so a third client only watches
+    let mut watcher = Client::connect(&addr, None, false).unwrap();
+    assert_eq!(watcher.seat, 0);
+
+    let p1 = thread::spawn(move || drop_everything(&mut c1));
+    let p2 = thread::spawn(move || drop_everything(&mut c2));
+
+    let (final1, _) = p1.join().unwrap();
+    let (final2, _) = p2.join().unwrap();
+    let game = server.join().unwrap();
+
+    assert!(!game.running);
+    assert!(game.loser.is_some());
+    assert_eq!(final1, final2);
+    assert_eq!(final1, game.snapshot());
+
+    let mut last = None;
+    let mut events = Vec::new();
+    while let Some(msg) = watcher.recv() {
+        match msg {
+            ServerMsg::State { state } => last = Some(state),
+            ServerMsg::Event { event } => events.push(event),
+            other => panic!("spectator got {:?}", other),
+        }
+    }
+    assert_eq!(last.as_ref(), Some(&final1));
+    assert_eq!(events.last(), Some(&GameEvent::GameOver { loser: game.loser.unwrap() }));
+}
+
+#[test]
+fn spectators_are_read_only_and_lag_behind() {
+    let mut server = Server::bind("127.0.0.1:0").unwrap();
+    server.set_spectator_delay(Duration::from_millis(300));
+    let addr = server.local_addr().unwrap().to_string();
+    let server = thread::spawn(move || {
+        let mut game = new_game();
+        server.run(&mut game, &mut [None, None]).unwrap();
+    });
+
+    // the delay is the host's: this spectator asks for nothing
+    let mut watcher = Client::connect(&addr, None, true).unwrap();
+    assert_eq!(watcher.seat, 0);
+
+    // spectating does not take a seat
+    let mut c1 = Client::connect(&addr, None, false).unwrap();
+    let mut c2 = Client::connect(&addr, None, false).unwrap();
+    assert_eq!((c1.seat, c2.seat), (1, 2));
+
+    watcher.send_move(Move::Drop, 1).unwrap();
+    match watcher.recv() {
+        Some(ServerMsg::Error { message }) => assert_eq!(message, "spectators cannot move"),
+        other => panic!("expected an error before any delayed state, got {:?}", other),
+    }
+
+    let p1 = thread::spawn(move || (drop_everything(&mut c1).0, Instant::now()));
+    let p2 = thread::spawn(move || drop_everything(&mut c2));
+
+    let mut last = None;
+    while let Some(msg) = watcher.recv() {
+        if let ServerMsg::State { state } = msg { last = Some(state); }
+    }
+    let watched_at = Instant::now();
+
+    let (final_player, played_at) = p1.join().unwrap();
+    p2.join().unwrap();
+    server.join().unwrap();
+
+    assert_eq!(last.as_ref(), Some(&final_player));
+    assert!(watched_at >= played_at + Duration::from_millis(250));
+}
+
+#[test]
+fn moves_out_of_turn_are_rejected() {
+    let (addr, server) = start_server();
+    let c1 = Client::connect(&addr, None, false).unwrap();
+    let mut c2 = Client::connect(&addr, None, false).unwrap();
+
+    // both seats taken: the game starts with player 1 to move
+    let state = loop {
+        if let Some(ServerMsg::State { state }) = c2.recv() { break state; }
+    };
+    assert_eq!(state.current_player, 1);
+
+    c2.send_move(Move::Drop, 1).unwrap();
+    let reply = c2.recv().unwrap();
+    assert!(matches!(reply, ServerMsg::Error { ref message } if message == "not your turn"), "{:?}", reply);
+
+    // leaving mid-game forfeits
+    drop(c1);
+    let game = server.join().unwrap();
+    assert_eq!(game.loser, Some(1));
+}
+
+/// Waits for the next state, skipping events.
+fn next_state(client: &mut Client) -> GameSnapshot {
+    loop {
+        match client.recv() {
+            Some(ServerMsg::State { state }) => return state,
+            Some(ServerMsg::Event { .. }) => {}
+            other => panic!("expected a state, got {:?}", other),
+        }
+    }
+}
+
+#[test]
+fn blinded_player_gets_the_hidden_cells_masked() {
+    let server = Server::bind("127.0.0.1:0").unwrap();
+    let addr = server.local_addr().unwrap().to_string();
+    let handle = thread::spawn(move || {
+        let mut game = new_game();
+        game.p1.effects.push(Effect::blind());
+        server.run(&mut game, &mut [None, None]).unwrap();
+    });
+
+    let mut c1 = Client::connect(&addr, None, false).unwrap();
+    let mut c2 = Client::connect(&addr, None, false).unwrap();
+    let (seen1, seen2) = (next_state(&mut c1), next_state(&mut c2));
+
+    let hidden = |view: &tetris::snapshot::BoardView| -> Vec<char> {
+        view.matrix().iter().enumerate()
+            .flat_map(|(r, row)| row.iter().enumerate().filter(move |&(c, _)| in_blind(r, c)).map(|(_, &ch)| ch).collect::<Vec<_>>())
+            .collect()
+    };
+    assert!(seen1.board(1).blind);
+    assert!(hidden(seen1.board(1)).iter().all(|&ch| ch == '?'));
+    // the opponent still sees the board as it is
+    assert!(!hidden(seen2.board(1)).contains(&'?'));
+    assert!(!hidden(seen1.board(2)).contains(&'?'));
+
+    drop(c1);
+    drop(c2);
+    handle.join().unwrap();
+}
+
+#[test]
+fn oversized_repeat_is_rejected() {
+    let (addr, server) = start_server();
+    let mut c1 = Client::connect(&addr, None, false).unwrap();
+    let c2 = Client::connect(&addr, None, false).unwrap();
+    let before = next_state(&mut c1);
+
+    c1.send_move(Move::Cw, 2_000_000_000).unwrap();
+    let reply = c1.recv().unwrap();
+    assert!(matches!(reply, ServerMsg::Error { ref message } if *message == format!("repeat must be at most {}", MAX_REPEAT)), "{:?}", reply);
+    assert_eq!(next_state(&mut c1), before);
+
+    drop(c1);
+    drop(c2);
+    server.join().unwrap();
+}
+
+#[test]
+fn overlong_lines_drop_the_client() {
+    let (addr, server) = start_server();
+    let mut raw = TcpStream::connect(&addr).unwrap();
+    raw.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+    // the host stops reading partway, so the write itself may fail
+    let _ = raw.write_all(&vec![b'x'; MAX_LINE * 4]);
+    let mut buf = [0; 64];
+    match raw.read(&mut buf) {
+        Ok(n) => assert_eq!(n, 0, "got {:?}", String::from_utf8_lossy(&buf[..n])),
+        Err(e) => assert!(!matches!(e.kind(), std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut), "{}", e),
+    }
+
+    // the host carries on and seats the next two clients
+    let mut c1 = Client::connect(&addr, None, false).unwrap();
+    let c2 = Client::connect(&addr, None, false).unwrap();
+    assert_eq!((c1.seat, c2.seat), (1, 2));
+    next_state(&mut c1);
+
+    drop(c1);
+    drop(c2);
+    server.join().unwrap();
+}
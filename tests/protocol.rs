@@ -0,0 +1,82 @@
+use std::time::{Duration, Instant};
+
+use tetris::controller::{Controller, RandomController};
+use tetris::game::Game;
+use tetris::player::Move;
+use tetris::level::LevelSet;
+use tetris::protocol::ExternalBot;
+use tetris::sim::{self, SimOptions};
+
+fn options(max_pieces: u32) -> SimOptions {
+    SimOptions {
+        seed: 7,
+        level: 0,
+        script1: "tetris_sequence1.txt".to_string(),
+        script2: "tetris_sequence2.txt".to_string(),
+        max_pieces,
+        levels: LevelSet::builtin(),
+    }
+}
+
+#[test]
+fn protobot_plays_a_game() {
+    let mut bot = ExternalBot::spawn(env!("CARGO_BIN_EXE_protobot"), Duration::from_secs(5)).unwrap();
+    let mut random = RandomController::new(1);
+    let result = sim::play_game(&options(40), &mut random, &mut bot).unwrap();
+
+    assert!(result.stats[1].pieces_placed > 0);
+    assert_eq!(bot.faults, 0);
+    assert_eq!(bot.name.as_deref(), Some("protobot"));
+}
+
+#[test]
+fn slow_bot_times_out_and_game_continues() {
+    // Answers the handshake, then never replies again.
+    let cmd = r#"read l; echo '{"type":"ready"}'; sleep 30"#;
+    let mut bot = ExternalBot::spawn(cmd, Duration::from_millis(100)).unwrap();
+    let mut random = RandomController::new(1);
+
+    let start = Instant::now();
+    let result = sim::play_game(&options(10), &mut random, &mut bot).unwrap();
+
+    assert!(result.stats[1].pieces_placed > 0);
+    assert!(bot.faults >= 1);
+    assert!(start.elapsed() < Duration::from_secs(10));
+}
+
+#[test]
+fn malformed_replies_are_counted() {
+    let cmd = r#"while read l; do echo 'not json'; done"#;
+    let mut bot = ExternalBot::spawn(cmd, Duration::from_millis(500)).unwrap();
+    let mut random = RandomController::new(1);
+    let result = sim::play_game(&options(6), &mut random, &mut bot).unwrap();
+
+    assert!(result.stats[1].pieces_placed > 0);
+    assert!(bot.faults >= 2);
+}
+
+#[test]
+fn missing_program_still_plays() {
+    let mut bot = ExternalBot::spawn("exit 0", Duration::from_millis(200)).unwrap();
+    let mut random = RandomController::new(1);
+    let result = sim::play_game(&options(4), &mut random, &mut bot).unwrap();
+
+    assert!(result.stats[1].pieces_placed > 0);
+    assert!(bot.faults >= 1);
+}
+
+#[test]
+fn late_reply_is_not_taken_for_the_next_request() {
+    // Misses the timeout on the first suggestion (left), answers the rest in time (right).
+    let cmd = r#"id() { echo "$1" | sed -n 's/.*"id":\([0-9]*\).*/\1/p'; }
+        read l; echo '{"type":"ready"}'
+        read l; sleep 0.4; echo "{\"type\":\"suggestion\",\"id\":$(id "$l"),\"moves\":[\"left\",\"drop\"]}"
+        while read l; do echo "{\"type\":\"suggestion\",\"id\":$(id "$l"),\"moves\":[\"right\",\"drop\"]}"; done"#;
+    let mut bot = ExternalBot::spawn(cmd, Duration::from_millis(300)).unwrap();
+    let mut game = Game::new(Some(7), 0, "tetris_sequence1.txt".to_string(), "tetris_sequence2.txt".to_string()).unwrap();
+
+    assert_eq!(bot.next_move(&game, 1), Move::Drop);
+    game.apply_move(1, Move::Drop);
+    assert_eq!(bot.next_move(&game, 1), Move::Right);
+    assert_eq!(bot.faults, 1);
+}
@@ -0,0 +1,124 @@
+use serde_json::Value;
+use tetris::block::{Block, BlockKind};
+use tetris::game::Game;
+use tetris::stats::{self, PlayerStats};
+
+/// Two pieces and a double for one player, with holes opened and then filled.
+fn sample() -> PlayerStats {
+    let mut s = PlayerStats::new();
+    s.record_move();
+    s.record_move();
+    s.record_lock(BlockKind::I, 1, 0);
+    s.record_settled(0);
+    s.record_move();
+    s.record_lock(BlockKind::T, 3, 2);
+    s.record_clear(2);
+    s.record_settled(2);
+    s
+}
+
+#[test]
+fn locks_clears_and_holes_are_counted() {
+    let mut s = sample();
+    assert_eq!(s.pieces_placed, 2);
+    assert_eq!(s.pieces_by_kind[&'I'], 1);
+    assert_eq!(s.pieces_by_kind[&'T'], 1);
+    assert_eq!(s.pieces_by_kind[&'O'], 0);
+    assert_eq!(s.max_stack_height, 3);
+    assert_eq!(s.holes_created, 2);
+    assert_eq!((s.lines_cleared, s.singles, s.doubles, s.triples, s.tetrises), (2, 0, 1, 0, 0));
+
+    // holes only count once: filling one in and leaving the other adds nothing
+    s.record_lock(BlockKind::O, 2, 1);
+    s.record_settled(1);
+    assert_eq!(s.holes_created, 2);
+    // new holes count from what the last landing left
+    s.record_lock(BlockKind::L, 2, 4);
+    s.record_settled(4);
+    assert_eq!(s.holes_created, 5);
+    assert_eq!(s.max_stack_height, 3);
+
+    s.record_clear(0);
+    s.record_clear(1);
+    s.record_clear(3);
+    s.record_clear(4);
+    assert_eq!((s.lines_cleared, s.singles, s.doubles, s.triples, s.tetrises), (10, 1, 1, 1, 1));
+    assert_eq!(s.pieces_placed, 4);
+}
+
+#[test]
+fn ratios_are_zero_before_anything_happens() {
+    let s = PlayerStats::new();
+    assert_eq!(s.pieces_per_move(), 0.0);
+    assert_eq!(s.lines_per_piece(), 0.0);
+    let s = sample();
+    assert_eq!(s.pieces_per_move(), 2.0 / 3.0);
+    assert_eq!(s.lines_per_piece(), 1.0);
+}
+
+#[test]
+fn csv_has_a_header_and_one_row_per_player() {
+    let (a, b) = (sample(), PlayerStats::new());
+    let csv = stats::to_csv(&[(&a, 120), (&b, 0)]);
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(lines.len(), 3);
+
+    let header: Vec<&str> = lines[0].split(',').collect();
+    assert_eq!(&header[..10], ["player", "score", "pieces_placed", "pieces_T", "pieces_S", "pieces_Z", "pieces_I", "pieces_J", "pieces_L", "pieces_O"]);
+    assert_eq!(header.last(), Some(&"lines_per_piece"));
+    for line in &lines[1..] {
+        assert_eq!(line.split(',').count(), header.len(), "{}", line);
+    }
+
+    let row: Vec<&str> = lines[1].split(',').collect();
+    let field = |name: &str| row[header.iter().position(|h| *h == name).unwrap()];
+    assert_eq!(field("player"), "1");
+    assert_eq!(field("score"), "120");
+    assert_eq!(field("pieces_I"), "1");
+    assert_eq!(field("doubles"), "1");
+    assert_eq!(field("moves"), "3");
+    assert_eq!(field("pieces_per_move"), "0.6667");
+    assert!(lines[2].starts_with("2,0,0,"));
+}
+
+#[test]
+fn json_lists_both_players_with_their_stats() {
+    let (a, b) = (sample(), PlayerStats::new());
+    let json: Value = serde_json::from_str(&stats::to_json(&[(&a, 120), (&b, 0)])).unwrap();
+    let players = json.as_array().unwrap();
+    assert_eq!(players.len(), 2);
+
+    let p1 = &players[0];
+    assert_eq!(p1["player"], 1);
+    assert_eq!(p1["score"], 120);
+    assert_eq!(p1["pieces_placed"], 2);
+    assert_eq!(p1["pieces_by_kind"]["T"], 1);
+    assert_eq!(p1["lines_cleared"], 2);
+    assert_eq!(p1["holes_created"], 2);
+    assert_eq!(p1["lines_per_piece"], 1.0);
+    assert!(p1.get("last_holes").is_none());
+    assert_eq!(players[1]["player"], 2);
+    assert_eq!(players[1]["pieces_placed"], 0);
+}
+
+#[test]
+fn specials_count_only_once_they_take_effect() {
+    let mut game = Game::new(Some(1), 1, "tetris_sequence1.txt".into(), "tetris_sequence2.txt".into()).unwrap();
+    game.quiet = true;
+    game.apply_special_action(1, "heavy", None);
+    game.apply_special_action(1, "force", Some("X"));
+    assert_eq!((game.p1.stats.specials_sent, game.p2.stats.specials_received), (1, 1));
+
+    // a forced block with no room to spawn ends the game without being sent
+    let kind = if game.p2.cur.kind == BlockKind::I { BlockKind::O } else { BlockKind::I };
+    let cur: Vec<(i32, i32)> = game.p2.cur.cells.iter().map(|c| (c.r, c.c)).collect();
+    for cell in Block::new(kind, 0).cells {
+        if !cur.contains(&(cell.r, cell.c)) {
+            game.p2.grid.set_cell(cell.r as usize, cell.c as usize, '*', 99);
+        }
+    }
+    game.apply_special_action(1, "force", Some(&kind.to_char().to_string()));
+    assert!(!game.running);
+    assert_eq!(game.loser, Some(2));
+    assert_eq!((game.p1.stats.specials_sent, game.p2.stats.specials_received), (1, 1));
+}
@@ -0,0 +1,146 @@
+use std::collections::BTreeSet;
+use std::time::Duration;
+
+use tetris::tournament::{bracket_slots, elimination_winner, round_robin, standings, GameRecord, MatchOptions, MatchResult, Ratings};
+
+/// A one-game match between entrants `a` and `b` with these scores.
+fn result(a: usize, b: usize, scores: [i32; 2]) -> MatchResult {
+    let winner = if scores[0] > scores[1] { Some(0) } else if scores[1] > scores[0] { Some(1) } else { None };
+    MatchResult {
+        round: 0,
+        players: [a, b],
+        games: vec![GameRecord { seed: 1, winner, scores, lines: [0, 0], pieces: 0 }],
+    }
+}
+
+#[test]
+fn round_robin_pairs_everyone_exactly_once() {
+    for n in 2..=9 {
+        let rounds = round_robin(n);
+        assert_eq!(rounds.len(), if n % 2 == 0 { n - 1 } else { n }, "{} entrants", n);
+
+        let mut met = BTreeSet::new();
+        for pairs in &rounds {
+            let mut seen = BTreeSet::new();
+            for &[a, b] in pairs {
+                assert!(a != b && a < n && b < n, "{} entrants: bad pair {:?}", n, [a, b]);
+                assert!(seen.insert(a) && seen.insert(b), "{} entrants: someone plays twice in a round", n);
+                assert!(met.insert((a.min(b), a.max(b))), "{} entrants: {} and {} meet twice", n, a, b);
+            }
+            // with an odd count exactly one entrant sits out
+            assert_eq!(seen.len(), n - n % 2, "{} entrants", n);
+        }
+        assert_eq!(met.len(), n * (n - 1) / 2, "{} entrants", n);
+    }
+}
+
+#[test]
+fn byes_go_to_the_top_seeds() {
+    for n in [3usize, 5, 6, 7, 9, 12] {
+        let size = n.next_power_of_two();
+        let slots = bracket_slots(size);
+        assert_eq!(slots.iter().copied().collect::<BTreeSet<_>>(), (0..size).collect(), "{} entrants", n);
+
+        let mut byes = Vec::new();
+        for pair in slots.chunks(2) {
+            assert!(pair[0] < n || pair[1] < n, "{} entrants: two byes meet", n);
+            if pair[1] >= n {
+                byes.push(pair[0]);
+            } else if pair[0] >= n {
+                byes.push(pair[1]);
+            }
+        }
+        byes.sort();
+        assert_eq!(byes, (0..size - n).collect::<Vec<_>>(), "{} entrants", n);
+    }
+    // seeds 1 and 2 are in opposite halves
+    let slots = bracket_slots(8);
+    assert!(slots[..4].contains(&0) && slots[4..].contains(&1));
+}
+
+#[test]
+fn elo_updates_are_zero_sum() {
+    let mut ratings = Ratings::default();
+    ratings.update("a", "b", 1.0);
+    assert_eq!(ratings.get("a"), Ratings::INITIAL + Ratings::K / 2.0);
+    assert_eq!(ratings.get("b"), Ratings::INITIAL - Ratings::K / 2.0);
+
+    for (a, b, score) in [("b", "a", 1.0), ("a", "c", 0.5), ("c", "b", 0.0), ("a", "b", 0.0)] {
+        let before = ratings.get(a) + ratings.get(b);
+        ratings.update(a, b, score);
+        assert!((ratings.get(a) + ratings.get(b) - before).abs() < 1e-9, "{} vs {}", a, b);
+    }
+    let total: f64 = ratings.ratings.values().sum();
+    assert!((total - 3.0 * Ratings::INITIAL).abs() < 1e-9);
+}
+
+#[test]
+fn ratings_survive_a_save_and_load() {
+    let path = std::env::temp_dir().join(format!("tetris-ratings-{}.json", std::process::id()));
+    let path = path.to_str().unwrap();
+    let _ = std::fs::remove_file(path);
+    assert!(Ratings::load(path).unwrap().ratings.is_empty());
+
+    let mut ratings = Ratings::default();
+    ratings.update("alice", "medium", 1.0);
+    ratings.update("medium", "pybot", 0.5);
+    ratings.save(path).unwrap();
+    let loaded = Ratings::load(path).unwrap();
+    std::fs::remove_file(path).unwrap();
+    assert_eq!(loaded.ratings, ratings.ratings);
+}
+
+#[test]
+fn standings_rank_by_points_then_score_difference() {
+    let results = [
+        result(0, 1, [100, 300]),
+        result(2, 3, [500, 100]),
+        result(0, 3, [200, 200]),
+        result(1, 2, [250, 250]),
+        result(0, 2, [400, 300]),
+        result(1, 3, [100, 0]),
+    ];
+    let table = standings(4, &results);
+    // 2 and 0 both have 1.5 points; 2 is +300, 0 is -100
+    let order: Vec<usize> = table.iter().map(|s| s.entrant).collect();
+    assert_eq!(order, [1, 2, 0, 3]);
+    assert_eq!(table.iter().map(|s| s.points()).collect::<Vec<_>>(), [2.5, 1.5, 1.5, 0.5]);
+    assert!(table.iter().all(|s| s.played == 3));
+}
+
+#[test]
+fn match_seeds_wrap_instead_of_overflowing() {
+    let opts = MatchOptions {
+        base_seed: u64::MAX,
+        level: 0,
+        script1: String::new(),
+        script2: String::new(),
+        max_pieces: 0,
+        games_per_match: 2,
+        bot_timeout: Duration::ZERO,
+    };
+    assert_eq!(opts.seed(0, 0), u64::MAX);
+    assert_eq!(opts.seed(0, 1), 0);
+    assert_eq!(opts.seed(1, 1), 1000);
+}
+
+#[test]
+fn drawn_elimination_matches_go_to_the_higher_seed() {
+    // entrant i is seed i; in round one seed 7 upsets seed 0 and seed 4 beats seed 3
+    let rank: Vec<usize> = (0..8).collect();
+    let slots = bracket_slots(8);
+    assert_eq!(&slots[..4], [0, 7, 3, 4]);
+    assert_eq!(elimination_winner(&result(0, 7, [100, 200]), &rank), 7);
+    assert_eq!(elimination_winner(&result(3, 4, [300, 400]), &rank), 4);
+
+    // the second-round match between them is drawn on games and score
+    let draw = result(7, 4, [250, 250]);
+    assert_eq!(elimination_winner(&draw, &rank), 4);
+    assert_eq!(elimination_winner(&result(4, 7, [250, 250]), &rank), 4);
+
+    // a higher total score still beats the better seed
+    let game = |winner, scores| GameRecord { seed: 1, winner: Some(winner), scores, lines: [0, 0], pieces: 0 };
+    let split = MatchResult { round: 1, players: [7, 4], games: vec![game(0, [300, 100]), game(1, [100, 200])] };
+    assert_eq!(split.winner(), None);
+    assert_eq!(elimination_winner(&split, &rank), 7);
+}